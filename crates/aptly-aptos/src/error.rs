@@ -0,0 +1,128 @@
+use reqwest::StatusCode;
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+/// Why a node request failed. Callers that only propagate can keep using
+/// `?` into `anyhow`; callers that handle specific cases (most often a
+/// missing resource) match on the variant instead of the message text.
+#[derive(Debug)]
+pub enum AptosError {
+    /// 404, e.g. `resource_not_found` or `transaction_not_found`. Holds the
+    /// response body.
+    NotFound(String),
+    /// 429 that outlasted the retries. Holds the response body.
+    RateLimited(String),
+    /// A 5xx status and the response body.
+    ServerError(u16, String),
+    /// Any other non-success status and the response body.
+    Api(u16, String),
+    /// The node did not answer within the client's request timeout.
+    Timeout { host: String, after: Duration },
+    /// The request could not be sent or its body could not be read.
+    Transport {
+        context: String,
+        source: Box<dyn Error + Send + Sync>,
+    },
+    /// The response body was not valid JSON.
+    Deserialize(serde_json::Error),
+}
+
+impl AptosError {
+    /// Classifies a non-success response by status.
+    pub(crate) fn from_status(status: StatusCode, body: String) -> Self {
+        match status {
+            StatusCode::NOT_FOUND => Self::NotFound(body),
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited(body),
+            status if status.is_server_error() => Self::ServerError(status.as_u16(), body),
+            status => Self::Api(status.as_u16(), body),
+        }
+    }
+
+    pub(crate) fn transport(
+        context: impl Into<String>,
+        source: impl Into<Box<dyn Error + Send + Sync>>,
+    ) -> Self {
+        Self::Transport {
+            context: context.into(),
+            source: source.into(),
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Self::NotFound(_))
+    }
+
+    /// The HTTP status, for errors that come from a response.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::NotFound(_) => Some(404),
+            Self::RateLimited(_) => Some(429),
+            Self::ServerError(status, _) | Self::Api(status, _) => Some(*status),
+            Self::Timeout { .. } | Self::Transport { .. } | Self::Deserialize(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for AptosError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(body)
+            | Self::RateLimited(body)
+            | Self::ServerError(_, body)
+            | Self::Api(_, body) => write!(
+                f,
+                "API error (status {}): {body}",
+                self.status().unwrap_or_default()
+            ),
+            Self::Timeout { host, after } => {
+                write!(f, "request to {host} timed out after {}s", after.as_secs())
+            }
+            Self::Transport { context, .. } => f.write_str(context),
+            Self::Deserialize(_) => f.write_str("failed to parse response JSON"),
+        }
+    }
+}
+
+impl Error for AptosError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Transport { source, .. } => Some(source.as_ref()),
+            Self::Deserialize(source) => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_statuses_and_keeps_message_format() {
+        let err = AptosError::from_status(StatusCode::NOT_FOUND, "{}".to_owned());
+        assert!(err.is_not_found());
+        assert_eq!(err.to_string(), "API error (status 404): {}");
+
+        let err = AptosError::from_status(StatusCode::SERVICE_UNAVAILABLE, "down".to_owned());
+        assert!(matches!(err, AptosError::ServerError(503, _)));
+        assert!(matches!(
+            AptosError::from_status(StatusCode::TOO_MANY_REQUESTS, String::new()),
+            AptosError::RateLimited(_)
+        ));
+        assert_eq!(
+            AptosError::from_status(StatusCode::BAD_REQUEST, String::new()).status(),
+            Some(400)
+        );
+
+        let err = AptosError::Timeout {
+            host: "api.mainnet.aptoslabs.com".to_owned(),
+            after: Duration::from_secs(30),
+        };
+        assert_eq!(
+            err.to_string(),
+            "request to api.mainnet.aptoslabs.com timed out after 30s"
+        );
+        assert_eq!(err.status(), None);
+    }
+}
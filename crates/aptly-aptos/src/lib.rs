@@ -1,59 +1,627 @@
 use anyhow::{anyhow, Context, Result};
-use reqwest::blocking::{Client, Response};
-use reqwest::StatusCode;
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{NoProxy, Proxy, StatusCode};
 use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+mod error;
+mod type_string;
+
+pub use error::AptosError;
+pub use type_string::{
+    contains_unresolved_type_param, format_type_string, substitute_type_parameters,
+    validate_type_string,
+};
+
+/// How much of a response body `get_top_level_string_field` reads before giving up.
+const FIELD_PREFIX_LIMIT: u64 = 8 * 1024;
+/// Retries after a 429/502/503/504 unless overridden with [`AptosClient::with_retries`].
+const DEFAULT_MAX_RETRIES: u32 = 3;
+/// First backoff delay; doubles on every further retry.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(200);
+/// Longest `Retry-After` wait honored, so a proxy cannot stall a run for hours.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(5);
+/// In-flight requests for [`AptosClient::get_json_batch`] unless overridden
+/// with [`AptosClient::with_concurrency`].
+pub const DEFAULT_CONCURRENCY: usize = 8;
+/// Keep-alive probe interval for pooled connections.
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+/// How long an idle pooled connection is kept for reuse.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+/// Node request timeout when [`HttpConfig::timeout`] is unset.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Bound on establishing a connection, separate from the request timeout.
+/// A shorter request timeout also caps it; see [`connect_timeout`].
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The connect limit that applies under `request_timeout`: [`CONNECT_TIMEOUT`],
+/// or the request timeout when that is shorter.
+fn connect_timeout(request_timeout: Option<Duration>) -> Duration {
+    request_timeout.map_or(CONNECT_TIMEOUT, |timeout| CONNECT_TIMEOUT.min(timeout))
+}
+
+/// Transport settings applied to every HTTP client aptly builds: the node
+/// API client as well as auxiliary fetches such as labels and traces.
+#[derive(Debug, Clone, Default)]
+pub struct HttpConfig {
+    /// Skip TLS certificate verification. Unsafe; meant only for local/dev
+    /// nodes with self-signed certificates.
+    pub accept_invalid_certs: bool,
+    /// Per-request timeout. `None` keeps reqwest's default, except for
+    /// [`AptosClient`], which falls back to [`DEFAULT_REQUEST_TIMEOUT`].
+    pub timeout: Option<Duration>,
+    /// Route all requests through this proxy, still honoring `NO_PROXY`.
+    /// Without it, `HTTPS_PROXY`/`HTTP_PROXY`/`NO_PROXY` apply as usual.
+    pub proxy: Option<String>,
+}
+
+impl HttpConfig {
+    pub fn build_client(&self) -> Result<Client> {
+        let mut builder = Client::builder()
+            .danger_accept_invalid_certs(self.accept_invalid_certs)
+            .tcp_keepalive(TCP_KEEPALIVE)
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .connect_timeout(connect_timeout(self.timeout));
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            let proxy = Proxy::all(proxy)
+                .with_context(|| format!("invalid proxy URL `{proxy}`"))?
+                .no_proxy(NoProxy::from_env());
+            builder = builder.proxy(proxy);
+        }
+        builder.build().context("failed to build HTTP client")
+    }
+}
 
 pub struct AptosClient {
     base_url: String,
+    /// `host[:port]` of `base_url`, for timeout errors.
+    host: String,
     http: Client,
+    http_config: HttpConfig,
+    timeout: Duration,
+    cache: Option<Mutex<ResponseCache>>,
+    prune_check: bool,
+    max_retries: u32,
+    api_key: Option<String>,
+    concurrency: usize,
 }
 
 impl AptosClient {
     pub fn new(base_url: &str) -> Result<Self> {
+        Self::with_config(base_url, &HttpConfig::default())
+    }
+
+    pub fn with_config(base_url: &str, config: &HttpConfig) -> Result<Self> {
         let base_url = base_url.trim().trim_end_matches('/').to_owned();
         if base_url.is_empty() {
             return Err(anyhow!("rpc url cannot be empty"));
         }
 
-        let http = Client::builder()
-            .build()
-            .context("failed to build HTTP client")?;
-        Ok(Self { base_url, http })
+        let host = reqwest::Url::parse(&base_url)
+            .ok()
+            .and_then(|url| {
+                let host = url.host_str()?.to_owned();
+                Some(match url.port() {
+                    Some(port) => format!("{host}:{port}"),
+                    None => host,
+                })
+            })
+            .unwrap_or_else(|| base_url.clone());
+        let timeout = config.timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+        let http = HttpConfig {
+            timeout: Some(timeout),
+            ..config.clone()
+        }
+        .build_client()?;
+        Ok(Self {
+            base_url,
+            host,
+            http,
+            http_config: config.clone(),
+            timeout,
+            cache: None,
+            prune_check: true,
+            max_retries: DEFAULT_MAX_RETRIES,
+            api_key: None,
+            concurrency: DEFAULT_CONCURRENCY,
+        })
+    }
+
+    /// The transport settings this client was built with, for auxiliary
+    /// requests that should behave the same way.
+    pub fn http_config(&self) -> &HttpConfig {
+        &self.http_config
+    }
+
+    /// Enables or disables [`AptosClient::ensure_version_available`]
+    /// (enabled by default).
+    pub fn with_prune_check(mut self, enabled: bool) -> Self {
+        self.prune_check = enabled;
+        self
+    }
+
+    /// Fails with the node's served range when `version` has been pruned or
+    /// is not yet committed, instead of letting the read fail opaquely.
+    pub fn ensure_version_available(&self, version: u64) -> Result<()> {
+        if !self.prune_check {
+            return Ok(());
+        }
+
+        let ledger = self.get_json_cached("/")?;
+        let field = |name: &str| -> Option<u64> {
+            match ledger.get(name)? {
+                Value::String(value) => value.parse().ok(),
+                Value::Number(value) => value.as_u64(),
+                _ => None,
+            }
+        };
+        let (Some(oldest), Some(latest)) =
+            (field("oldest_ledger_version"), field("ledger_version"))
+        else {
+            return Ok(());
+        };
+
+        if version < oldest {
+            return Err(anyhow!(
+                "version {version} is pruned; node serves [{oldest}, {latest}]"
+            ));
+        }
+        if version > latest {
+            return Err(anyhow!(
+                "version {version} is not committed yet; node serves [{oldest}, {latest}]"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Retries rate-limited (429) and gateway (502/503/504) responses up to
+    /// `max` times with exponential backoff, honoring `Retry-After`.
+    pub fn with_retries(mut self, max: u32) -> Self {
+        self.max_retries = max;
+        self
+    }
+
+    /// Sends `key` as `Authorization: Bearer <key>` on every node request.
+    /// `None` or an empty key sends no authorization header.
+    pub fn with_api_key(mut self, key: Option<String>) -> Self {
+        self.api_key = key.filter(|key| !key.trim().is_empty());
+        self
     }
 
-    pub fn get_json(&self, path: &str) -> Result<Value> {
+    /// Bounds the requests [`AptosClient::get_json_batch`] keeps in flight,
+    /// and what multi-call commands should use for their own fan-out.
+    /// Values below 1 are treated as 1.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Memoizes up to `capacity` responses from the `*_cached` read methods
+    /// for the lifetime of this client.
+    pub fn with_response_cache(mut self, capacity: usize) -> Self {
+        self.cache = Some(Mutex::new(ResponseCache::new(capacity)));
+        self
+    }
+
+    pub fn get_json(&self, path: &str) -> Result<Value, AptosError> {
         let url = self.endpoint(path);
         let response = self
-            .http
-            .get(&url)
-            .send()
-            .with_context(|| format!("request failed: GET {url}"))?;
+            .send_with_retries(|| self.http.get(&url))
+            .map_err(|err| self.send_error("GET", &url, err))?;
         self.handle_response(response)
     }
 
-    pub fn post_json(&self, path: &str, body: &Value) -> Result<Value> {
+    pub fn post_json(&self, path: &str, body: &Value) -> Result<Value, AptosError> {
         let url = self.endpoint(path);
         let response = self
-            .http
-            .post(&url)
-            .json(body)
-            .send()
-            .with_context(|| format!("request failed: POST {url}"))?;
+            .send_with_retries(|| self.http.post(&url).json(body))
+            .map_err(|err| self.send_error("POST", &url, err))?;
         self.handle_response(response)
     }
 
+    /// GETs every path with at most [`AptosClient::concurrency`] requests in
+    /// flight over the shared connection pool. Results are in `paths` order.
+    pub fn get_json_batch(&self, paths: &[String]) -> Vec<Result<Value, AptosError>> {
+        parallel_map(paths, self.concurrency, |path| self.get_json(path))
+    }
+
+    /// Reads only the start of the response body and returns the top-level
+    /// string `field` if it appears there, without downloading the rest.
+    /// Returns `Ok(None)` when the field is not in the first few KiB; callers
+    /// should then fall back to [`AptosClient::get_json`].
+    pub fn get_top_level_string_field(
+        &self,
+        path: &str,
+        field: &str,
+    ) -> Result<Option<String>, AptosError> {
+        let url = self.endpoint(path);
+        let response = self
+            .send_with_retries(|| self.http.get(&url))
+            .map_err(|err| self.send_error("GET", &url, err))?;
+        let status = response.status();
+        if status != StatusCode::OK {
+            let text = response.text().map_err(|err| self.body_error(err))?;
+            return Err(AptosError::from_status(status, self.redact(&text)));
+        }
+
+        let mut prefix = Vec::new();
+        response
+            .take(FIELD_PREFIX_LIMIT)
+            .read_to_end(&mut prefix)
+            .map_err(|err| self.body_error(err))?;
+        Ok(find_top_level_string_field(
+            &String::from_utf8_lossy(&prefix),
+            field,
+        ))
+    }
+
+    /// Like [`AptosClient::get_json`], but served from the response cache when
+    /// the same path was already read. Only use for idempotent reads.
+    pub fn get_json_cached(&self, path: &str) -> Result<Value, AptosError> {
+        self.cached(format!("GET {path}"), || self.get_json(path))
+    }
+
+    /// Like [`AptosClient::post_json`], but served from the response cache when
+    /// the same path and body were already sent. Only use for idempotent reads
+    /// such as `/view`.
+    pub fn post_json_cached(&self, path: &str, body: &Value) -> Result<Value, AptosError> {
+        self.cached(format!("POST {path} {body}"), || self.post_json(path, body))
+    }
+
+    fn cached(
+        &self,
+        key: String,
+        fetch: impl FnOnce() -> Result<Value, AptosError>,
+    ) -> Result<Value, AptosError> {
+        let Some(cache) = self.cache.as_ref() else {
+            return fetch();
+        };
+
+        if let Some(value) = lock_cache(cache).get(&key) {
+            return Ok(value);
+        }
+        let value = fetch()?;
+        lock_cache(cache).insert(key, value.clone());
+        Ok(value)
+    }
+
+    /// [`send_with_retries`] with this client's retry budget and API key.
+    fn send_with_retries(&self, build: impl Fn() -> RequestBuilder) -> reqwest::Result<Response> {
+        send_with_retries(self.max_retries, || {
+            let request = build();
+            match &self.api_key {
+                Some(key) => request.bearer_auth(key),
+                None => request,
+            }
+        })
+    }
+
+    /// Reports timeouts as [`AptosError::Timeout`], naming the host and the
+    /// limit, instead of a generic transport failure.
+    fn send_error(&self, method: &str, url: &str, err: reqwest::Error) -> AptosError {
+        if err.is_timeout() {
+            // reqwest flags a connect timeout as both; report the limit that fired.
+            let after = if err.is_connect() {
+                connect_timeout(Some(self.timeout))
+            } else {
+                self.timeout
+            };
+            return self.timeout_error(after);
+        }
+        AptosError::transport(
+            format!("request failed: {method} {}", self.redact(url)),
+            err,
+        )
+    }
+
+    /// Like [`AptosClient::send_error`], for failures while reading the body.
+    fn body_error(&self, err: impl Into<Box<dyn Error + Send + Sync>>) -> AptosError {
+        let err = err.into();
+        let timed_out = match err.downcast_ref::<reqwest::Error>() {
+            Some(err) => err.is_timeout(),
+            None => err
+                .downcast_ref::<io::Error>()
+                .is_some_and(|err| err.kind() == io::ErrorKind::TimedOut),
+        };
+        if timed_out {
+            return self.timeout_error(self.timeout);
+        }
+        AptosError::transport("failed to read response body", err)
+    }
+
+    fn timeout_error(&self, after: Duration) -> AptosError {
+        AptosError::Timeout {
+            host: self.host.clone(),
+            after,
+        }
+    }
+
+    /// Masks the API key in text bound for error messages, in case a node or
+    /// proxy echoes it back.
+    fn redact(&self, text: &str) -> String {
+        match &self.api_key {
+            Some(key) => text.replace(key.as_str(), "<redacted>"),
+            None => text.to_owned(),
+        }
+    }
+
     fn endpoint(&self, path: &str) -> String {
         format!("{}/{}", self.base_url, path.trim_start_matches('/'))
     }
 
-    fn handle_response(&self, response: Response) -> Result<Value> {
+    fn handle_response(&self, response: Response) -> Result<Value, AptosError> {
         let status = response.status();
-        let text = response.text().context("failed to read response body")?;
+        let text = response.text().map_err(|err| self.body_error(err))?;
 
         if status != StatusCode::OK && status != StatusCode::ACCEPTED {
-            return Err(anyhow!("API error (status {}): {}", status.as_u16(), text));
+            return Err(AptosError::from_status(status, self.redact(&text)));
+        }
+
+        serde_json::from_str(&text).map_err(AptosError::Deserialize)
+    }
+}
+
+/// Applies `f` to every item on up to `concurrency` scoped threads and
+/// returns the results in `items` order. Meant for blocking round trips
+/// such as node requests.
+pub fn parallel_map<T: Sync, R: Send>(
+    items: &[T],
+    concurrency: usize,
+    f: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let slots: Vec<Mutex<Option<R>>> = items.iter().map(|_| Mutex::new(None)).collect();
+    let next = AtomicUsize::new(0);
+    let workers = items.len().min(concurrency.max(1));
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let result = f(item);
+                *slots[index]
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(result);
+            });
         }
+    });
+
+    // A panicking worker re-panics when the scope ends, so every slot is
+    // filled by now.
+    slots
+        .into_iter()
+        .filter_map(|slot| {
+            slot.into_inner()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+        })
+        .collect()
+}
+
+/// Sends a fresh request from `build` per attempt, so POST bodies are never
+/// reused, and retries rate-limited and gateway responses up to `max_retries`
+/// times with exponential backoff, honoring `Retry-After`. The last response
+/// is returned as-is once retries run out.
+pub fn send_with_retries(
+    max_retries: u32,
+    build: impl Fn() -> RequestBuilder,
+) -> reqwest::Result<Response> {
+    let mut attempt = 0;
+    loop {
+        let response = build().send()?;
+        if attempt >= max_retries || !is_retryable_status(response.status()) {
+            return Ok(response);
+        }
+        let delay = retry_after(response.headers())
+            .unwrap_or_else(|| RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt)));
+        thread::sleep(delay);
+        attempt += 1;
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// The delay requested by a `Retry-After: <seconds>` header, capped at
+/// [`MAX_RETRY_AFTER`]. HTTP-date values are ignored in favor of the regular
+/// backoff.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let seconds = headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(Duration::from_secs(seconds).min(MAX_RETRY_AFTER))
+}
+
+/// Scans a (possibly truncated) JSON object for `"field": "<string>"` at depth 1.
+fn find_top_level_string_field(json: &str, field: &str) -> Option<String> {
+    let mut chars = json.chars().peekable();
+    let mut depth = 0usize;
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' | '[' => depth += 1,
+            '}' | ']' => depth = depth.saturating_sub(1),
+            '"' => {
+                let key = read_json_string(&mut chars)?;
+                while chars.peek().is_some_and(|ch| ch.is_whitespace()) {
+                    chars.next();
+                }
+                if depth != 1 || key != field || chars.peek() != Some(&':') {
+                    continue;
+                }
+                chars.next();
+                while chars.peek().is_some_and(|ch| ch.is_whitespace()) {
+                    chars.next();
+                }
+                if chars.next() != Some('"') {
+                    return None;
+                }
+                return read_json_string(&mut chars);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Reads the rest of a JSON string after its opening quote and decodes its
+/// escapes. `None` if the string is cut off or malformed.
+fn read_json_string(chars: &mut impl Iterator<Item = char>) -> Option<String> {
+    let mut raw = String::from('"');
+    while let Some(ch) = chars.next() {
+        raw.push(ch);
+        match ch {
+            '"' => return serde_json::from_str(&raw).ok(),
+            '\\' => raw.push(chars.next()?),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Bounded response memo; evicts the oldest entry once `capacity` is reached.
+struct ResponseCache {
+    capacity: usize,
+    entries: HashMap<String, Value>,
+    order: VecDeque<String>,
+}
+
+impl ResponseCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<Value> {
+        self.entries.get(key).cloned()
+    }
+
+    fn insert(&mut self, key: String, value: Value) {
+        if self.capacity == 0 || self.entries.contains_key(&key) {
+            return;
+        }
+        while self.entries.len() >= self.capacity {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+        self.order.push_back(key.clone());
+        self.entries.insert(key, value);
+    }
+}
+
+fn lock_cache(cache: &Mutex<ResponseCache>) -> std::sync::MutexGuard<'_, ResponseCache> {
+    // A panic mid-insert cannot leave the memo inconsistent enough to matter.
+    cache
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn finds_top_level_string_fields() {
+        let find = |json: &str| find_top_level_string_field(json, "hash");
+
+        assert_eq!(
+            find(r#"{"note": "say \"hash\": \"0xno\"", "hash": "0xab"}"#).as_deref(),
+            Some("0xab")
+        );
+        assert_eq!(find(r#"{"hash":"0xab"}"#).as_deref(), Some("0xab"));
+        assert_eq!(find(r#"{"hash": "0x\u0061b"}"#).as_deref(), Some("0xab"));
+        assert_eq!(find(r#"{"h\u0061sh": "0xcd"}"#).as_deref(), Some("0xcd"));
+        assert_eq!(
+            find(r#"{"tx": {"hash": "0xinner"}, "events": [{"hash": "0xe"}], "hash": "0xouter"}"#)
+                .as_deref(),
+            Some("0xouter")
+        );
+        assert_eq!(find(r#"{"tx": {"hash": "0xinner"}, "version": "1"}"#), None);
+        assert_eq!(find(r#"{"version": "1", "hash": "0xab"#), None);
+        assert_eq!(find(r#"{"hash": 5}"#), None);
+    }
+
+    #[test]
+    fn connect_timeout_never_exceeds_request_timeout() {
+        assert_eq!(connect_timeout(None), CONNECT_TIMEOUT);
+        assert_eq!(
+            connect_timeout(Some(Duration::from_secs(3))),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            connect_timeout(Some(Duration::from_secs(30))),
+            CONNECT_TIMEOUT
+        );
+    }
+
+    #[test]
+    fn parallel_map_keeps_input_order() {
+        let items: Vec<u64> = (0..50).collect();
+        let doubled = parallel_map(&items, 4, |item| {
+            thread::sleep(Duration::from_micros(50 - item));
+            item * 2
+        });
+        assert_eq!(
+            doubled,
+            items.iter().map(|item| item * 2).collect::<Vec<_>>()
+        );
+        assert!(parallel_map(&[] as &[u64], 4, |item| *item).is_empty());
+        assert_eq!(parallel_map(&[1, 2], 0, |item| item + 1), [2, 3]);
+    }
+
+    #[test]
+    fn retries_only_rate_limits_and_gateway_errors() {
+        for status in [429, 502, 503, 504] {
+            assert!(is_retryable_status(StatusCode::from_u16(status).unwrap()));
+        }
+        for status in [200, 400, 404, 500, 501] {
+            assert!(!is_retryable_status(StatusCode::from_u16(status).unwrap()));
+        }
+    }
 
-        serde_json::from_str(&text).context("failed to parse response JSON")
+    #[test]
+    fn caps_retry_after_seconds_and_ignores_dates() {
+        let headers = |value: &'static str| {
+            HeaderMap::from_iter([(RETRY_AFTER, HeaderValue::from_static(value))])
+        };
+        assert_eq!(retry_after(&headers(" 2 ")), Some(Duration::from_secs(2)));
+        assert_eq!(retry_after(&headers("3600")), Some(MAX_RETRY_AFTER));
+        assert_eq!(retry_after(&headers("Wed, 21 Oct 2015 07:28:00 GMT")), None);
+        assert_eq!(retry_after(&HeaderMap::new()), None);
     }
 }
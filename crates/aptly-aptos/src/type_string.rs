@@ -0,0 +1,293 @@
+use anyhow::{anyhow, bail, Context, Result};
+
+const PRIMITIVE_TYPES: &[&str] = &[
+    "bool", "u8", "u16", "u32", "u64", "u128", "u256", "i8", "i16", "i32", "i64", "i128", "i256",
+    "address", "signer",
+];
+
+/// Pre-checks a Move type string such as `0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>`
+/// for the common hand-typing mistakes (unbalanced `<>`, empty or stray-space
+/// segments, a missing `address::module::name` part) so callers can report
+/// them consistently before handing the string to a real parser or the node.
+pub fn validate_type_string(input: &str) -> Result<()> {
+    check_type(input.trim()).with_context(|| format!("invalid type `{input}`"))
+}
+
+/// Lays out a Move type string with each type argument on its own line,
+/// indented under its generic type:
+///
+/// ```text
+/// 0x1::coin::CoinInfo<
+///   0x1::aptos_coin::AptosCoin
+/// >
+/// ```
+pub fn format_type_string(input: &str) -> Result<String> {
+    validate_type_string(input)?;
+    let mut out = String::new();
+    write_type(&mut out, input.trim(), 0)?;
+    Ok(out)
+}
+
+fn write_type(out: &mut String, ty: &str, depth: usize) -> Result<()> {
+    let Some(open) = ty.find('<') else {
+        out.push_str(ty);
+        return Ok(());
+    };
+    // `validate_type_string` guarantees the closing `>` is last.
+    let args = split_type_arguments(&ty[open + 1..ty.len() - 1])?;
+    out.push_str(ty[..open].trim_end());
+    out.push_str("<\n");
+    for (index, arg) in args.iter().enumerate() {
+        out.push_str(&"  ".repeat(depth + 1));
+        write_type(out, arg, depth + 1)?;
+        if index + 1 < args.len() {
+            out.push(',');
+        }
+        out.push('\n');
+    }
+    out.push_str(&"  ".repeat(depth));
+    out.push('>');
+    Ok(())
+}
+
+fn check_type(ty: &str) -> Result<()> {
+    if ty.is_empty() {
+        bail!("empty type");
+    }
+
+    let (head, generics) = match ty.find('<') {
+        Some(open) => {
+            let Some(inner) = ty[open + 1..].strip_suffix('>') else {
+                bail!("unbalanced `<>`");
+            };
+            (ty[..open].trim_end(), Some(inner))
+        }
+        None if ty.contains('>') => bail!("unbalanced `<>`"),
+        None => (ty, None),
+    };
+    if head.chars().any(char::is_whitespace) {
+        bail!("unexpected whitespace in `{head}`");
+    }
+
+    if PRIMITIVE_TYPES.contains(&head) {
+        if generics.is_some() {
+            bail!("`{head}` takes no type arguments");
+        }
+        return Ok(());
+    }
+
+    if head == "vector" {
+        let args =
+            split_type_arguments(generics.ok_or_else(|| anyhow!("missing `<T>` after `vector`"))?)?;
+        if args.len() != 1 {
+            bail!(
+                "`vector` takes exactly one type argument, got {}",
+                args.len()
+            );
+        }
+        return check_type(args[0]);
+    }
+
+    let segments: Vec<&str> = head.split("::").collect();
+    if segments.len() != 3 || segments.iter().any(|segment| segment.is_empty()) {
+        bail!("expected `address::module::name`, got `{head}`");
+    }
+    let address = segments[0].strip_prefix("0x").unwrap_or_default();
+    if address.is_empty() || !address.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        bail!("expected a 0x-prefixed hex address, got `{}`", segments[0]);
+    }
+
+    if let Some(generics) = generics {
+        for arg in split_type_arguments(generics)? {
+            check_type(arg)?;
+        }
+    }
+    Ok(())
+}
+
+/// Splits the inside of `<...>` on top-level commas.
+fn split_type_arguments(inner: &str) -> Result<Vec<&str>> {
+    let mut args = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (index, ch) in inner.char_indices() {
+        match ch {
+            '<' => depth += 1,
+            '>' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| anyhow!("unbalanced `<>`"))?
+            }
+            ',' if depth == 0 => {
+                args.push(inner[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        bail!("unbalanced `<>`");
+    }
+    args.push(inner[start..].trim());
+
+    if args.iter().any(|arg| arg.is_empty()) {
+        bail!("empty type argument");
+    }
+    Ok(args)
+}
+
+/// Replaces `T0`, `T1`, .. placeholders in an ABI type such as
+/// `0x1::object::Object<T0>` with the matching entry of `type_arguments`.
+/// Placeholders without a matching argument are left as-is.
+pub fn substitute_type_parameters(param: &str, type_arguments: &[String]) -> String {
+    let chars: Vec<char> = param.chars().collect();
+    let mut resolved = String::with_capacity(param.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        if let Some((digits_start, end)) = type_param_placeholder_span(&chars, i) {
+            let index: Option<usize> = chars[digits_start..end]
+                .iter()
+                .collect::<String>()
+                .parse::<usize>()
+                .ok();
+            if let Some(type_arg) = index.and_then(|value| type_arguments.get(value)) {
+                resolved.push_str(type_arg);
+            } else {
+                for ch in &chars[i..end] {
+                    resolved.push(*ch);
+                }
+            }
+            i = end;
+            continue;
+        }
+
+        resolved.push(chars[i]);
+        i += 1;
+    }
+
+    resolved
+}
+
+/// Whether `param` still holds a `T<n>` placeholder.
+pub fn contains_unresolved_type_param(param: &str) -> bool {
+    let chars: Vec<char> = param.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if let Some((_, end)) = type_param_placeholder_span(&chars, i) {
+            if end > i {
+                return true;
+            }
+            i = end;
+            continue;
+        }
+        i += 1;
+    }
+
+    false
+}
+
+fn type_param_placeholder_span(chars: &[char], start: usize) -> Option<(usize, usize)> {
+    if chars.get(start).copied()? != 'T' {
+        return None;
+    }
+    if !chars.get(start + 1).is_some_and(char::is_ascii_digit) {
+        return None;
+    }
+
+    let prev_ok = start == 0 || !is_type_param_ident_char(chars[start - 1]);
+    if !prev_ok {
+        return None;
+    }
+
+    let mut end = start + 1;
+    while chars.get(end).is_some_and(char::is_ascii_digit) {
+        end += 1;
+    }
+
+    let next_ok = end == chars.len() || !is_type_param_ident_char(chars[end]);
+    if !next_ok {
+        return None;
+    }
+
+    Some((start + 1, end))
+}
+
+fn is_type_param_ident_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_well_formed_types() {
+        for ty in [
+            "u64",
+            "vector<u8>",
+            "0x1::aptos_coin::AptosCoin",
+            "0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>",
+            "0x1::pool::Pool<0x1::a::A, vector<0xcafe::b::B<u8>>>",
+        ] {
+            assert!(validate_type_string(ty).is_ok(), "{ty}");
+        }
+    }
+
+    #[test]
+    fn formats_nested_generics_one_argument_per_line() {
+        assert_eq!(
+            format_type_string("0x1::account::Account").unwrap(),
+            "0x1::account::Account"
+        );
+        assert_eq!(
+            format_type_string("0x1::coin::CoinInfo<0x1::aptos_coin::AptosCoin>").unwrap(),
+            "0x1::coin::CoinInfo<\n  0x1::aptos_coin::AptosCoin\n>"
+        );
+        assert_eq!(
+            format_type_string("0x1::pool::Pool<0x1::a::A, vector<0xcafe::b::B<u8>>>").unwrap(),
+            "0x1::pool::Pool<\n  0x1::a::A,\n  vector<\n    0xcafe::b::B<\n      u8\n    >\n  >\n>"
+        );
+        assert!(format_type_string("0x1::coin::CoinInfo<").is_err());
+    }
+
+    #[test]
+    fn explains_common_mistakes() {
+        let cause = |ty: &str| format!("{:#}", validate_type_string(ty).unwrap_err());
+        assert!(
+            cause("0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin").ends_with("unbalanced `<>`")
+        );
+        assert!(cause("0x1::coin::CoinStore<>").ends_with("empty type argument"));
+        assert!(cause("0x1::coin:: CoinStore")
+            .ends_with("unexpected whitespace in `0x1::coin:: CoinStore`"));
+        assert!(cause("coin::CoinStore")
+            .ends_with("expected `address::module::name`, got `coin::CoinStore`"));
+        assert!(cause("aptos_framework::coin::CoinStore").contains("0x-prefixed hex address"));
+        assert!(cause("u64<u8>").ends_with("`u64` takes no type arguments"));
+    }
+
+    #[test]
+    fn substitutes_generic_placeholders() {
+        let actual = substitute_type_parameters(
+            "0x1::object::Object<T0>",
+            &["0x1::fungible_asset::Metadata".to_owned()],
+        );
+        assert_eq!(actual, "0x1::object::Object<0x1::fungible_asset::Metadata>");
+    }
+
+    #[test]
+    fn substitutes_generics_without_touching_identifier_text() {
+        let actual = substitute_type_parameters(
+            "vector<T1>",
+            &["u8".to_owned(), "0x1::my::T0Coin".to_owned()],
+        );
+        assert_eq!(actual, "vector<0x1::my::T0Coin>");
+    }
+
+    #[test]
+    fn substitutes_only_token_placeholders() {
+        let actual = substitute_type_parameters("0x1::my::T0Coin<T0>", &["u8".to_owned()]);
+        assert_eq!(actual, "0x1::my::T0Coin<u8>");
+    }
+}
@@ -1,24 +1,53 @@
 use anyhow::{anyhow, Context, Result};
-use aptly_aptos::AptosClient;
-use clap::{Args, Subcommand};
+use aptly_aptos::{
+    format_type_string, parallel_map, validate_type_string, AptosClient, AptosError,
+};
+use clap::{Args, Subcommand, ValueEnum};
 use flate2::read::GzDecoder;
-use num_bigint::BigInt;
 use serde::Serialize;
 use serde_json::Value;
-use std::collections::HashMap;
-use std::io::Read;
-use std::str::FromStr;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{Read, Write};
 
+use crate::commands::address::{load_labels, LabelsCachePolicy};
+use crate::commands::asset::{format_amount, AssetResolver};
 use crate::commands::common::{
-    get_nested_string, parse_u64, shorten_addr, value_to_string, with_optional_ledger_version,
+    get_nested_string, normalize_hex_address, paired_fa_metadata, parse_u64, shorten_addr,
+    stream_all_pages, unwrap_options, value_to_string, with_optional_ledger_version,
+    KNOWN_COIN_FA_PAIRS, OBJECT_CORE_TYPE,
 };
+use crate::commands::decompile::fetch_account_modules;
+use crate::commands::tx::deposits_to;
+use crate::JsonArrayWriter;
 
 const PACKAGE_REGISTRY_TYPE: &str = "0x1::code::PackageRegistry";
-const FUNGIBLE_METADATA_TYPE: &str = "0x1::fungible_asset::Metadata";
+const MAX_TXS_PAGE_SIZE: u64 = 100;
+/// Guards `resolve-owner` against pathological ownership chains.
+const MAX_OWNER_HOPS: usize = 64;
+/// Well-known mainnet assets accepted by symbol in `account balance`.
+const KNOWN_ASSETS: &[(&str, &str)] = &[
+    ("APT", "0x1::aptos_coin::AptosCoin"),
+    (
+        "USDC",
+        "0xbae207659db88bea0cbead6da0ed00aac12edcdda169e591cd41c94180b46f3b",
+    ),
+    (
+        "USDT",
+        "0x357b0b74bc833e95a115ad22604854d6b0fca151cecd94111770e5d6ffc9dc2b",
+    ),
+    (
+        "lzUSDC",
+        "0xf22bede237a07e121b56d91a491eb7bcdfd1f5907926a9e58338f964a01b17fa::asset::USDC",
+    ),
+    (
+        "lzUSDT",
+        "0xf22bede237a07e121b56d91a491eb7bcdfd1f5907926a9e58338f964a01b17fa::asset::USDT",
+    ),
+];
 
 #[derive(Args)]
 #[command(
-    after_help = "Examples:\n  aptly account 0x1\n  aptly account resources 0x1\n  aptly account resource 0x1 0x1::coin::CoinInfo<0x1::aptos_coin::AptosCoin>\n  aptly account module 0x1 coin --abi\n  aptly account balance 0x1 0x1::aptos_coin::AptosCoin\n  aptly account txs 0x1 --limit 10\n  aptly account sends 0x1 --limit 50 --pretty\n  aptly account source-code 0x1 chain_id --raw\n\nIf source metadata is unavailable:\n  aptly decompile address <address>\n  aptly decompile module <address> <module_name>"
+    after_help = "Examples:\n  aptly account 0x1\n  aptly account resources 0x1\n  aptly account resources 0x1 --group-by module\n  aptly account resources 0x1 --changed-since 3000000000\n  aptly account resource 0x1 0x1::coin::CoinInfo<0x1::aptos_coin::AptosCoin>\n  aptly account resource 0x1 0x1::coin::CoinInfo<0x1::aptos_coin::AptosCoin> --unwrap-options\n  aptly account resource 0x1 0x1::coin::CoinInfo<0x1::aptos_coin::AptosCoin> --pretty-type\n  aptly account resource-changes 0x1 0x1::coin::CoinInfo<0x1::aptos_coin::AptosCoin> --from 3000000000 --to 3100000000\n  aptly account module 0x1 coin --abi\n  aptly account find-function 0x1 transfer --public-only\n  aptly account balance 0x1 0x1::aptos_coin::AptosCoin\n  aptly account balance 0x1 --asset USDC\n  aptly account balance 0x1 --asset APT --combined\n  aptly account balance 0x1 --all\n  aptly account auth-key 0x1\n  aptly account resolve-owner <object_address>\n  aptly account txs 0x1 --limit 10\n  aptly account txs 0x1 --count 500\n  aptly account txs 0x1 --all --max-pages 10\n  aptly account sends 0x1 --limit 50 --pretty\n  aptly account sends 0x1 --label --pretty\n  aptly account sends 0x1 --pretty --columns from,to,amount,asset,version\n  aptly account sends 0x1 --direction both --pretty --columns version,amount,asset,from,to\n  aptly account source-code 0x1 chain_id --raw\n  aptly account source-code 0x1 --package AptosFramework --list\n\nIf source metadata is unavailable:\n  aptly decompile address <address>\n  aptly decompile module <address> <module_name>"
 )]
 pub(crate) struct AccountCommand {
     #[command(subcommand)]
@@ -31,15 +60,35 @@ pub(crate) struct AccountCommand {
 #[derive(Subcommand)]
 pub(crate) enum AccountSubcommand {
     #[command(about = "List all Move resources under an account")]
-    Resources(AddressArg),
+    Resources(ResourcesArgs),
     #[command(about = "Read a Move resource by fully-qualified type")]
     Resource(ResourceArgs),
+    #[command(
+        name = "resource-changes",
+        about = "Diff a Move resource's data between two ledger versions"
+    )]
+    ResourceChanges(ResourceChangesArgs),
     #[command(about = "List all Move modules published under an account")]
     Modules(AddressArg),
     #[command(about = "Read a module, its ABI only, or its raw bytecode")]
     Module(ModuleArgs),
+    #[command(
+        name = "find-function",
+        about = "Find the modules under an account that expose a function by name"
+    )]
+    FindFunction(FindFunctionArgs),
     #[command(about = "Read fungible asset balance for an account address")]
     Balance(BalanceArgs),
+    #[command(
+        name = "auth-key",
+        about = "Show the authentication key, whether it was rotated, and rotation history"
+    )]
+    AuthKey(AuthKeyArgs),
+    #[command(
+        name = "resolve-owner",
+        about = "Follow an object's ownership chain to the account that ultimately owns it"
+    )]
+    ResolveOwner(ResolveOwnerArgs),
     #[command(about = "List account transactions (with --limit/--start pagination)")]
     Txs(TxsArgs),
     #[command(about = "Summarize outgoing transfers from account transactions")]
@@ -62,6 +111,36 @@ pub(crate) struct AddressArg {
     pub(crate) ledger_version: Option<u64>,
 }
 
+#[derive(Args)]
+pub(crate) struct ResourcesArgs {
+    /// Account address (`0x...`).
+    #[arg(value_name = "ADDRESS")]
+    pub(crate) address: String,
+    /// Read from a historical ledger version.
+    #[arg(long)]
+    pub(crate) ledger_version: Option<u64>,
+    /// Group resources into a map keyed by the type's address or `address::module`.
+    #[arg(long, value_enum)]
+    pub(crate) group_by: Option<ResourceGrouping>,
+    /// List resource types written by the account's own transactions at or
+    /// after this version, with the version each last changed.
+    #[arg(
+        long,
+        value_name = "VERSION",
+        conflicts_with_all = ["group_by", "ledger_version", "unwrap_options"]
+    )]
+    pub(crate) changed_since: Option<u64>,
+    /// Render `Option` values (`{"vec": [x]}` / `{"vec": []}`) as `x` / `null`.
+    #[arg(long, default_value_t = false)]
+    pub(crate) unwrap_options: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub(crate) enum ResourceGrouping {
+    Address,
+    Module,
+}
+
 #[derive(Args)]
 pub(crate) struct ResourceArgs {
     /// Account address (`0x...`).
@@ -73,6 +152,31 @@ pub(crate) struct ResourceArgs {
     /// Read from a historical ledger version.
     #[arg(long)]
     pub(crate) ledger_version: Option<u64>,
+    /// Render `Option` values (`{"vec": [x]}` / `{"vec": []}`) as `x` / `null`.
+    #[arg(long, default_value_t = false)]
+    pub(crate) unwrap_options: bool,
+    /// Also print the resource type to stderr with one type argument per line.
+    #[arg(long, default_value_t = false)]
+    pub(crate) pretty_type: bool,
+}
+
+#[derive(Args)]
+pub(crate) struct ResourceChangesArgs {
+    /// Account address (`0x...`).
+    #[arg(value_name = "ADDRESS")]
+    pub(crate) address: String,
+    /// Fully-qualified Move resource type.
+    #[arg(value_name = "RESOURCE_TYPE")]
+    pub(crate) resource_type: String,
+    /// Ledger version to diff from.
+    #[arg(long, value_name = "VERSION")]
+    pub(crate) from: u64,
+    /// Ledger version to diff to.
+    #[arg(long, value_name = "VERSION")]
+    pub(crate) to: u64,
+    /// Render `Option` values (`{"vec": [x]}` / `{"vec": []}`) as `x` / `null`.
+    #[arg(long, default_value_t = false)]
+    pub(crate) unwrap_options: bool,
 }
 
 #[derive(Args)]
@@ -94,19 +198,64 @@ pub(crate) struct ModuleArgs {
     pub(crate) bytecode: bool,
 }
 
+#[derive(Args)]
+pub(crate) struct FindFunctionArgs {
+    /// Account address (`0x...`).
+    #[arg(value_name = "ADDRESS")]
+    pub(crate) address: String,
+    /// Function name, without the module prefix.
+    #[arg(value_name = "FUNCTION_NAME")]
+    pub(crate) function_name: String,
+    /// Read from a historical ledger version.
+    #[arg(long)]
+    pub(crate) ledger_version: Option<u64>,
+    /// Only report functions with `public` visibility.
+    #[arg(long, default_value_t = false)]
+    pub(crate) public_only: bool,
+}
+
 #[derive(Args)]
 pub(crate) struct BalanceArgs {
     /// Account address (`0x...`).
     #[arg(value_name = "ADDRESS")]
     pub(crate) address: String,
-    /// Optional asset type tag; defaults to AptosCoin.
+    /// Optional asset type tag or FA metadata address; defaults to AptosCoin.
     #[arg(value_name = "ASSET_TYPE")]
     pub(crate) asset_type: Option<String>,
+    /// Asset type tag, FA metadata address, or well-known mainnet symbol
+    /// (APT, USDC, USDT, lzUSDC, lzUSDT).
+    #[arg(long, value_name = "ASSET", conflicts_with = "asset_type")]
+    pub(crate) asset: Option<String>,
+    /// Sum the legacy `CoinStore` and the paired fungible store balance,
+    /// with a breakdown, for assets mid coin-to-FA migration.
+    #[arg(long)]
+    pub(crate) combined: bool,
+    /// List every coin store plus the fungible stores of known and paired
+    /// assets, instead of a single asset.
+    #[arg(long, conflicts_with_all = ["asset_type", "asset", "combined"])]
+    pub(crate) all: bool,
+    /// With `--all`, keep assets whose balance is zero.
+    #[arg(long, requires = "all")]
+    pub(crate) include_zero: bool,
     /// Read from a historical ledger version.
     #[arg(long)]
     pub(crate) ledger_version: Option<u64>,
 }
 
+#[derive(Args)]
+pub(crate) struct AuthKeyArgs {
+    /// Account address (`0x...`).
+    #[arg(value_name = "ADDRESS")]
+    pub(crate) address: String,
+}
+
+#[derive(Args)]
+pub(crate) struct ResolveOwnerArgs {
+    /// Object address (`0x...`).
+    #[arg(value_name = "OBJECT_ADDR")]
+    pub(crate) address: String,
+}
+
 #[derive(Args)]
 pub(crate) struct TxsArgs {
     /// Account address (`0x...`).
@@ -118,6 +267,16 @@ pub(crate) struct TxsArgs {
     /// Start cursor (ledger version offset).
     #[arg(long, default_value_t = 0)]
     pub(crate) start: u64,
+    /// Follow pagination to stream up to N transactions. Pages forward from
+    /// `--start` when set, otherwise returns the latest N.
+    #[arg(long, value_name = "N", conflicts_with = "limit")]
+    pub(crate) count: Option<u64>,
+    /// Follow pagination from `--start` until the last page, as one array.
+    #[arg(long, conflicts_with_all = ["limit", "count"])]
+    pub(crate) all: bool,
+    /// With `--all`, stop after this many pages.
+    #[arg(long, default_value_t = 50, requires = "all")]
+    pub(crate) max_pages: u64,
 }
 
 #[derive(Args)]
@@ -128,9 +287,49 @@ pub(crate) struct SendsArgs {
     /// Maximum number of transactions to scan.
     #[arg(long, default_value_t = 25)]
     pub(crate) limit: u64,
+    /// Transfers sent by the account (`out`), received by it (`in`), or both.
+    /// `in` finds transactions through the account's `CoinStore` deposit
+    /// events, so fungible-asset-only transfers are not listed.
+    #[arg(long, value_enum, default_value_t = SendsDirection::Out)]
+    pub(crate) direction: SendsDirection,
     /// Render human-friendly decimal amounts and symbols.
     #[arg(long, default_value_t = false)]
     pub(crate) pretty: bool,
+    /// Round amounts half-up to at most N fractional digits (default: full precision).
+    #[arg(long, value_name = "N")]
+    pub(crate) precision: Option<u8>,
+    /// Annotate counterparties with known address labels (`from_label`/`to_label`).
+    #[arg(long, default_value_t = false)]
+    pub(crate) label: bool,
+    /// Fail on amounts that are not integers instead of printing them as-is.
+    #[arg(long, default_value_t = false)]
+    pub(crate) strict: bool,
+    /// With `--pretty`, the columns to show, in order.
+    #[arg(
+        long,
+        value_name = "COLUMNS",
+        value_delimiter = ',',
+        requires = "pretty",
+        default_value = "version,amount,asset,to,hash"
+    )]
+    pub(crate) columns: Vec<SendsColumn>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum SendsDirection {
+    Out,
+    In,
+    Both,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub(crate) enum SendsColumn {
+    Version,
+    Amount,
+    Asset,
+    From,
+    To,
+    Hash,
 }
 
 #[derive(Args)]
@@ -150,6 +349,9 @@ pub(crate) struct SourceCodeArgs {
     /// Print raw package/module/source JSON.
     #[arg(long, default_value_t = false)]
     pub(crate) raw: bool,
+    /// List `{package, module, has_source}` without decoding any source.
+    #[arg(long, default_value_t = false, conflicts_with = "raw")]
+    pub(crate) list: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -159,6 +361,26 @@ struct ModuleSource {
     source: String,
 }
 
+#[derive(Debug, Clone, Serialize)]
+struct ModuleSourceListing {
+    package: String,
+    module: String,
+    has_source: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct FunctionMatch {
+    module: String,
+    name: String,
+    visibility: String,
+    is_entry: bool,
+    is_view: bool,
+    generic_type_params: Value,
+    params: Value,
+    #[serde(rename = "return")]
+    returns: Value,
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct Transfer {
     from: String,
@@ -166,46 +388,136 @@ struct Transfer {
     amount: String,
     asset: String,
     version: u64,
+    hash: String,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    labels: Option<TransferLabels>,
 }
 
-#[derive(Debug, Clone, Default)]
-struct AssetMetadata {
+#[derive(Debug, Clone, Serialize)]
+struct TransferLabels {
+    from_label: Option<String>,
+    to_label: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct ChangedResource {
+    #[serde(rename = "type")]
+    resource_type: String,
+    last_changed_version: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct OwnerHop {
+    object: String,
+    owner: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct AuthKeyInfo {
+    address: String,
+    authentication_key: String,
+    /// False while the auth key still equals the address it was derived for.
+    rotated: bool,
+    /// Signature type of the account's most recent transaction, if any.
+    last_signature_scheme: Option<String>,
+    /// `0x1::account::KeyRotationEvent`s, when the node can serve them.
+    rotation_history: Option<Value>,
+}
+
+#[derive(Serialize)]
+struct ResourceDiff {
+    address: String,
+    resource_type: String,
+    from: u64,
+    to: u64,
+    exists_at_from: bool,
+    exists_at_to: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+    added: BTreeMap<String, Value>,
+    removed: BTreeMap<String, Value>,
+    changed: BTreeMap<String, ValueChange>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct ValueChange {
+    before: Value,
+    after: Value,
+}
+
+#[derive(Serialize)]
+struct AssetBalance {
+    asset: String,
     symbol: String,
+    amount: String,
     decimals: u8,
 }
 
+#[derive(Serialize)]
+struct CombinedBalance {
+    coin_type: String,
+    fa_metadata: Option<String>,
+    coin_balance: u64,
+    fa_balance: u64,
+    total: u128,
+}
+
 pub(crate) fn run_account(client: &AptosClient, command: AccountCommand) -> Result<()> {
     match (command.command, command.address) {
         (Some(AccountSubcommand::Resources(args)), _) => {
+            if let Some(since) = args.changed_since {
+                let changed = resources_changed_since(client, &args.address, since)?;
+                return crate::print_serialized(&changed);
+            }
             let path = with_optional_ledger_version(
+                client,
                 &format!("/accounts/{}/resources", args.address),
                 args.ledger_version,
-            );
-            let value = client.get_json(&path)?;
-            crate::print_pretty_json(&value)
+            )?;
+            let mut value = client.get_json(&path)?;
+            if args.unwrap_options {
+                unwrap_options(&mut value);
+            }
+            match args.group_by {
+                Some(grouping) => crate::print_serialized(&group_resources(value, grouping)?),
+                None => crate::print_pretty_json(&value),
+            }
         }
         (Some(AccountSubcommand::Resource(args)), _) => {
+            validate_type_string(&args.resource_type)?;
             let encoded = urlencoding::encode(&args.resource_type);
             let path = with_optional_ledger_version(
+                client,
                 &format!("/accounts/{}/resource/{encoded}", args.address),
                 args.ledger_version,
-            );
-            let value = client.get_json(&path)?;
+            )?;
+            let mut value = client.get_json(&path)?;
+            if args.unwrap_options {
+                unwrap_options(&mut value);
+            }
+            if args.pretty_type {
+                eprintln!("{}", pretty_resource_type(&value)?);
+            }
             crate::print_pretty_json(&value)
         }
+        (Some(AccountSubcommand::ResourceChanges(args)), _) => {
+            run_account_resource_changes(client, &args)
+        }
         (Some(AccountSubcommand::Modules(args)), _) => {
             let path = with_optional_ledger_version(
+                client,
                 &format!("/accounts/{}/modules", args.address),
                 args.ledger_version,
-            );
+            )?;
             let value = client.get_json(&path)?;
             crate::print_pretty_json(&value)
         }
         (Some(AccountSubcommand::Module(args)), _) => {
             let path = with_optional_ledger_version(
+                client,
                 &format!("/accounts/{}/module/{}", args.address, args.module_name),
                 args.ledger_version,
-            );
+            )?;
             let value = client.get_json(&path)?;
 
             if !args.abi && !args.bytecode {
@@ -220,29 +532,68 @@ pub(crate) fn run_account(client: &AptosClient, command: AccountCommand) -> Resu
             let bytecode = value.get("bytecode").cloned().unwrap_or(Value::Null);
             crate::print_pretty_json(&bytecode)
         }
+        (Some(AccountSubcommand::FindFunction(args)), _) => {
+            run_account_find_function(client, &args)
+        }
         (Some(AccountSubcommand::Balance(args)), _) => {
-            let asset_type = args
-                .asset_type
-                .unwrap_or_else(|| "0x1::aptos_coin::AptosCoin".to_owned());
+            if args.all {
+                let balances = fetch_all_balances(
+                    client,
+                    &args.address,
+                    args.include_zero,
+                    args.ledger_version,
+                )?;
+                return crate::print_serialized(&balances);
+            }
+            let asset_type = match args.asset.or(args.asset_type) {
+                Some(asset) => resolve_asset_symbol(&asset)?,
+                None => "0x1::aptos_coin::AptosCoin".to_owned(),
+            };
+            if args.combined {
+                let balance = fetch_combined_balance(
+                    client,
+                    &args.address,
+                    &asset_type,
+                    args.ledger_version,
+                )?;
+                return crate::print_serialized(&balance);
+            }
             let encoded = urlencoding::encode(&asset_type);
             let path = with_optional_ledger_version(
+                client,
                 &format!("/accounts/{}/balance/{encoded}", args.address),
                 args.ledger_version,
-            );
+            )?;
             let value = client.get_json(&path)?;
             crate::print_pretty_json(&value)
         }
         (Some(AccountSubcommand::Txs(args)), _) => {
-            let mut path = format!(
-                "/accounts/{}/transactions?limit={}",
-                args.address, args.limit
-            );
-            if args.start > 0 {
-                path.push_str(&format!("&start={}", args.start));
+            if args.all {
+                let mut writer = JsonArrayWriter::stdout();
+                stream_all_pages(
+                    args.start,
+                    MAX_TXS_PAGE_SIZE,
+                    args.max_pages,
+                    &mut writer,
+                    |start, limit| {
+                        fetch_account_txs_page(client, &args.address, Some(start), limit)
+                    },
+                )?;
+                return writer.finish();
             }
-            let value = client.get_json(&path)?;
+            if let Some(count) = args.count {
+                let mut writer = JsonArrayWriter::stdout();
+                stream_account_txs_count(client, &args.address, args.start, count, &mut writer)?;
+                return writer.finish();
+            }
+            let start = (args.start > 0).then_some(args.start);
+            let value = client.get_json(&account_txs_path(&args.address, start, args.limit))?;
             crate::print_pretty_json(&value)
         }
+        (Some(AccountSubcommand::AuthKey(args)), _) => run_account_auth_key(client, &args),
+        (Some(AccountSubcommand::ResolveOwner(args)), _) => {
+            run_account_resolve_owner(client, &args)
+        }
         (Some(AccountSubcommand::Sends(args)), _) => run_account_sends(client, &args),
         (Some(AccountSubcommand::SourceCode(args)), _) => run_account_source_code(client, &args),
         (None, Some(address)) => {
@@ -253,25 +604,605 @@ pub(crate) fn run_account(client: &AptosClient, command: AccountCommand) -> Resu
     }
 }
 
+/// Maps a well-known symbol to its asset type; type tags and addresses pass through.
+fn resolve_asset_symbol(asset: &str) -> Result<String> {
+    if asset.starts_with("0x") || asset.contains("::") {
+        return Ok(asset.to_owned());
+    }
+
+    KNOWN_ASSETS
+        .iter()
+        .find(|(symbol, _)| symbol.eq_ignore_ascii_case(asset))
+        .map(|(_, asset_type)| (*asset_type).to_owned())
+        .ok_or_else(|| {
+            let known: Vec<&str> = KNOWN_ASSETS.iter().map(|(symbol, _)| *symbol).collect();
+            anyhow!(
+                "unknown asset symbol `{asset}`; known symbols: {}. Pass a full type tag or FA metadata address instead",
+                known.join(", ")
+            )
+        })
+}
+
+fn run_account_resource_changes(client: &AptosClient, args: &ResourceChangesArgs) -> Result<()> {
+    validate_type_string(&args.resource_type)?;
+    let fetch = |version: u64| -> Result<Option<Value>> {
+        let encoded = urlencoding::encode(&args.resource_type);
+        let path = with_optional_ledger_version(
+            client,
+            &format!("/accounts/{}/resource/{encoded}", args.address),
+            Some(version),
+        )?;
+        match client.get_json(&path) {
+            Ok(mut resource) => {
+                if args.unwrap_options {
+                    unwrap_options(&mut resource);
+                }
+                Ok(Some(resource.get("data").cloned().unwrap_or(Value::Null)))
+            }
+            Err(AptosError::NotFound(_)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    };
+    let before = fetch(args.from)?;
+    let after = fetch(args.to)?;
+
+    let note = match (&before, &after) {
+        (None, None) => {
+            return Err(anyhow!(
+                "{} does not exist at {} at version {} or {}",
+                args.resource_type,
+                args.address,
+                args.from,
+                args.to
+            ))
+        }
+        (None, Some(_)) => Some(format!(
+            "resource does not exist at version {}; all fields are reported as added",
+            args.from
+        )),
+        (Some(_), None) => Some(format!(
+            "resource does not exist at version {}; all fields are reported as removed",
+            args.to
+        )),
+        (Some(_), Some(_)) => None,
+    };
+
+    let mut diff = ResourceDiff {
+        address: args.address.clone(),
+        resource_type: args.resource_type.clone(),
+        from: args.from,
+        to: args.to,
+        exists_at_from: before.is_some(),
+        exists_at_to: after.is_some(),
+        note,
+        added: BTreeMap::new(),
+        removed: BTreeMap::new(),
+        changed: BTreeMap::new(),
+    };
+    let empty = Value::Object(serde_json::Map::new());
+    diff_values(
+        "",
+        before.as_ref().unwrap_or(&empty),
+        after.as_ref().unwrap_or(&empty),
+        &mut diff,
+    );
+    crate::print_serialized(&diff)
+}
+
+/// Records differences between `before` and `after` under dotted field
+/// paths, descending into objects; arrays and scalars compare as a whole.
+fn diff_values(path: &str, before: &Value, after: &Value, diff: &mut ResourceDiff) {
+    let (Value::Object(before_map), Value::Object(after_map)) = (before, after) else {
+        if before != after {
+            diff.changed.insert(
+                path.to_owned(),
+                ValueChange {
+                    before: before.clone(),
+                    after: after.clone(),
+                },
+            );
+        }
+        return;
+    };
+
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+    for (key, old) in before_map {
+        match after_map.get(key) {
+            Some(new) => diff_values(&join(key), old, new, diff),
+            None => {
+                diff.removed.insert(join(key), old.clone());
+            }
+        }
+    }
+    for (key, new) in after_map {
+        if !before_map.contains_key(key) {
+            diff.added.insert(join(key), new.clone());
+        }
+    }
+}
+
+/// Every `CoinStore<T>` at `address`, plus the primary fungible stores of the
+/// well-known FA assets and of the FAs paired with those coins. The node API
+/// cannot enumerate arbitrary owned stores, so other FAs are not listed.
+fn fetch_all_balances(
+    client: &AptosClient,
+    address: &str,
+    include_zero: bool,
+    ledger_version: Option<u64>,
+) -> Result<Vec<AssetBalance>> {
+    let path = with_optional_ledger_version(
+        client,
+        &format!("/accounts/{address}/resources"),
+        ledger_version,
+    )?;
+    let resources = client.get_json(&path)?;
+    let resources = resources
+        .as_array()
+        .ok_or_else(|| anyhow!("unexpected resources response format"))?;
+
+    let resolver = AssetResolver::new(client);
+    let mut balances = Vec::new();
+    let mut fa_candidates: Vec<String> = KNOWN_ASSETS
+        .iter()
+        .map(|(_, asset)| *asset)
+        .chain(KNOWN_COIN_FA_PAIRS.iter().map(|(_, metadata)| *metadata))
+        .filter(|asset| !asset.contains("::"))
+        .map(str::to_owned)
+        .collect();
+
+    let coin_stores: Vec<(String, String)> = resources
+        .iter()
+        .filter_map(|resource| {
+            let resource_type = get_nested_string(resource, &["type"]);
+            let coin_type = resource_type
+                .strip_prefix("0x1::coin::CoinStore<")?
+                .strip_suffix('>')?
+                .to_owned();
+            Some((
+                coin_type,
+                get_nested_string(resource, &["data", "coin", "value"]),
+            ))
+        })
+        .collect();
+    let paired = parallel_map(&coin_stores, client.concurrency(), |(coin_type, _)| {
+        paired_fa_metadata(client, coin_type, ledger_version)
+    });
+    for ((coin_type, amount), paired) in coin_stores.into_iter().zip(paired) {
+        match paired {
+            Ok(Some(paired)) => fa_candidates.push(paired),
+            Ok(None) => {}
+            Err(err) => {
+                eprintln!("warning: {err:#}; its FA balance may be missing");
+            }
+        }
+        let metadata = resolver.coin(&coin_type);
+        balances.push(AssetBalance {
+            asset: coin_type,
+            symbol: metadata.symbol,
+            amount,
+            decimals: metadata.decimals,
+        });
+    }
+
+    let mut seen = HashSet::new();
+    fa_candidates.retain(|metadata_address| seen.insert(normalize_hex_address(metadata_address)));
+    let paths = fa_candidates
+        .iter()
+        .map(|metadata_address| {
+            with_optional_ledger_version(
+                client,
+                &format!("/accounts/{address}/balance/{metadata_address}"),
+                ledger_version,
+            )
+        })
+        .collect::<Result<Vec<_>>>()?;
+    for (metadata_address, fetched) in fa_candidates.into_iter().zip(client.get_json_batch(&paths))
+    {
+        let amount = match fetched {
+            Ok(value) => value_to_string(&value),
+            Err(err) => {
+                eprintln!("warning: skipping FA {metadata_address}: {err:#}");
+                continue;
+            }
+        };
+        let metadata = resolver.fungible_asset(&metadata_address);
+        balances.push(AssetBalance {
+            asset: metadata_address,
+            symbol: metadata.symbol,
+            amount,
+            decimals: metadata.decimals,
+        });
+    }
+
+    if !include_zero {
+        balances.retain(|balance| !balance.amount.trim_start_matches('0').is_empty());
+    }
+    Ok(balances)
+}
+
+fn fetch_combined_balance(
+    client: &AptosClient,
+    address: &str,
+    asset_type: &str,
+    ledger_version: Option<u64>,
+) -> Result<CombinedBalance> {
+    let (coin_type, fa_metadata) = if asset_type.contains("::") {
+        let metadata = paired_fa_metadata(client, asset_type, ledger_version)?;
+        (asset_type.to_owned(), metadata)
+    } else {
+        let metadata = normalize_hex_address(asset_type);
+        let coin_type = KNOWN_COIN_FA_PAIRS
+            .iter()
+            .find(|(_, paired)| normalize_hex_address(paired) == metadata)
+            .map(|(coin_type, _)| (*coin_type).to_owned())
+            .ok_or_else(|| {
+                anyhow!(
+                    "no known coin pairing for FA metadata {asset_type}; pass the coin type to --combined instead"
+                )
+            })?;
+        (coin_type, Some(asset_type.to_owned()))
+    };
+
+    let store_type = format!("0x1::coin::CoinStore<{coin_type}>");
+    let path = with_optional_ledger_version(
+        client,
+        &format!(
+            "/accounts/{address}/resource/{}",
+            urlencoding::encode(&store_type)
+        ),
+        ledger_version,
+    )?;
+    let coin_balance = match client.get_json(&path) {
+        Ok(resource) => parse_u64(resource.pointer("/data/coin/value").unwrap_or(&Value::Null))
+            .ok_or_else(|| anyhow!("{store_type} at {address} has no `coin.value`"))?,
+        Err(AptosError::NotFound(_)) => 0,
+        Err(err) => return Err(err.into()),
+    };
+
+    let fa_balance = match &fa_metadata {
+        Some(metadata) => {
+            let path = with_optional_ledger_version(
+                client,
+                &format!("/accounts/{address}/balance/{metadata}"),
+                ledger_version,
+            )?;
+            let value = client.get_json(&path)?;
+            parse_u64(&value)
+                .ok_or_else(|| anyhow!("unexpected balance response for {metadata}: {value}"))?
+        }
+        None => 0,
+    };
+
+    Ok(CombinedBalance {
+        coin_type,
+        fa_metadata,
+        coin_balance,
+        fa_balance,
+        total: u128::from(coin_balance) + u128::from(fa_balance),
+    })
+}
+
+/// The resource's `type`, laid out by [`format_type_string`].
+fn pretty_resource_type(resource: &Value) -> Result<String> {
+    let resource_type = resource
+        .get("type")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("resource has no `type` field"))?;
+    format_type_string(resource_type)
+}
+
+fn group_resources(
+    resources: Value,
+    grouping: ResourceGrouping,
+) -> Result<BTreeMap<String, Vec<Value>>> {
+    let Value::Array(resources) = resources else {
+        return Err(anyhow!("unexpected resources response format"));
+    };
+
+    let mut groups: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+    for resource in resources {
+        let resource_type = resource
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("resource missing `type` field"))?;
+        let segments = match grouping {
+            ResourceGrouping::Address => 1,
+            ResourceGrouping::Module => 2,
+        };
+        let key = resource_type
+            .splitn(segments + 1, "::")
+            .take(segments)
+            .collect::<Vec<_>>()
+            .join("::");
+        groups.entry(key).or_default().push(resource);
+    }
+    Ok(groups)
+}
+
+/// Scans the account's transactions newest-first back to `since`, keeping the
+/// latest version at which each resource type under the account was written.
+fn resources_changed_since(
+    client: &AptosClient,
+    address: &str,
+    since: u64,
+) -> Result<Vec<ChangedResource>> {
+    let account = normalize_hex_address(address);
+    let mut last_changed: HashMap<String, u64> = HashMap::new();
+
+    let mut page = fetch_account_txs_page(client, address, None, MAX_TXS_PAGE_SIZE)?;
+    while let Some(first) = page.first() {
+        let first_sequence_number = tx_sequence_number(first)?;
+        let mut reached_since = false;
+        for tx in &page {
+            let version = parse_u64(tx.get("version").unwrap_or(&Value::Null)).unwrap_or(0);
+            if version < since {
+                reached_since = true;
+                continue;
+            }
+            let changes = tx.get("changes").and_then(Value::as_array);
+            for change in changes.into_iter().flatten() {
+                if change.get("type").and_then(Value::as_str) != Some("write_resource") {
+                    continue;
+                }
+                let touches_account = change
+                    .get("address")
+                    .and_then(Value::as_str)
+                    .is_some_and(|changed| normalize_hex_address(changed) == account);
+                let resource_type = get_nested_string(change, &["data", "type"]);
+                if !touches_account || resource_type.is_empty() {
+                    continue;
+                }
+                let entry = last_changed.entry(resource_type).or_insert(version);
+                *entry = (*entry).max(version);
+            }
+        }
+
+        if reached_since || first_sequence_number == 0 {
+            break;
+        }
+        let limit = MAX_TXS_PAGE_SIZE.min(first_sequence_number);
+        page = fetch_account_txs_page(client, address, Some(first_sequence_number - limit), limit)?;
+    }
+
+    let mut changed: Vec<ChangedResource> = last_changed
+        .into_iter()
+        .map(|(resource_type, last_changed_version)| ChangedResource {
+            resource_type,
+            last_changed_version,
+        })
+        .collect();
+    changed.sort_by(|a, b| {
+        b.last_changed_version
+            .cmp(&a.last_changed_version)
+            .then_with(|| a.resource_type.cmp(&b.resource_type))
+    });
+    Ok(changed)
+}
+
+fn account_txs_path(address: &str, start: Option<u64>, limit: u64) -> String {
+    let mut path = format!("/accounts/{address}/transactions?limit={limit}");
+    if let Some(start) = start {
+        path.push_str(&format!("&start={start}"));
+    }
+    path
+}
+
+fn fetch_account_txs_page(
+    client: &AptosClient,
+    address: &str,
+    start: Option<u64>,
+    limit: u64,
+) -> Result<Vec<Value>> {
+    let value = client.get_json(&account_txs_path(address, start, limit))?;
+    match value {
+        Value::Array(txs) => Ok(txs),
+        _ => Err(anyhow!("unexpected transactions response format")),
+    }
+}
+
+fn tx_sequence_number(tx: &Value) -> Result<u64> {
+    parse_u64(tx.get("sequence_number").unwrap_or(&Value::Null))
+        .ok_or_else(|| anyhow!("transaction response missing `sequence_number` field"))
+}
+
+/// Streams up to `count` account transactions in ascending sequence order,
+/// one page at a time.
+fn stream_account_txs_count(
+    client: &AptosClient,
+    address: &str,
+    start: u64,
+    count: u64,
+    writer: &mut JsonArrayWriter,
+) -> Result<()> {
+    // Without a start cursor, anchor on the latest transaction and page
+    // forward from `count` before it so output stays ascending.
+    let (mut next, count) = if start > 0 {
+        (start, count)
+    } else {
+        let latest = fetch_account_txs_page(client, address, None, 1)?;
+        let Some(last) = latest.last() else {
+            return Ok(());
+        };
+        let total = tx_sequence_number(last)? + 1;
+        (total.saturating_sub(count), count.min(total))
+    };
+
+    let mut collected = 0u64;
+    while collected < count {
+        let limit = count.saturating_sub(collected).min(MAX_TXS_PAGE_SIZE);
+        let page = fetch_account_txs_page(client, address, Some(next), limit)?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        next = tx_sequence_number(last)? + 1;
+        collected += page.len() as u64;
+        for tx in &page {
+            writer.push(tx)?;
+        }
+        if (page.len() as u64) < limit {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn run_account_auth_key(client: &AptosClient, args: &AuthKeyArgs) -> Result<()> {
+    let account = client.get_json(&format!("/accounts/{}", args.address))?;
+    let authentication_key = account
+        .get("authentication_key")
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow!("account response missing `authentication_key` field"))?
+        .to_owned();
+
+    let last_signature_scheme = fetch_account_txs_page(client, &args.address, None, 1)
+        .ok()
+        .and_then(|txs| {
+            txs.last()
+                .and_then(|tx| tx.get("signature"))
+                .and_then(|signature| signature.get("type"))
+                .and_then(Value::as_str)
+                .map(str::to_owned)
+        });
+    let rotation_history = match client.get_json(&format!(
+        "/accounts/{}/events/0x1::account::Account/key_rotation_events",
+        args.address
+    )) {
+        Ok(events) => Some(events),
+        Err(AptosError::NotFound(_)) => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    let info = AuthKeyInfo {
+        rotated: normalize_hex_address(&authentication_key) != normalize_hex_address(&args.address),
+        address: args.address.clone(),
+        authentication_key,
+        last_signature_scheme,
+        rotation_history,
+    };
+    crate::print_serialized(&info)
+}
+
+fn run_account_resolve_owner(client: &AptosClient, args: &ResolveOwnerArgs) -> Result<()> {
+    let mut hops: Vec<OwnerHop> = Vec::new();
+    let mut current = args.address.clone();
+    while let Some(owner) = fetch_object_owner(client, &current)? {
+        if hops.len() >= MAX_OWNER_HOPS {
+            return Err(anyhow!(
+                "ownership chain of {} exceeds {MAX_OWNER_HOPS} hops",
+                args.address
+            ));
+        }
+        if hops
+            .iter()
+            .any(|hop| normalize_hex_address(&hop.object) == normalize_hex_address(&owner))
+        {
+            return Err(anyhow!("ownership cycle detected at {owner}"));
+        }
+        hops.push(OwnerHop {
+            object: current,
+            owner: owner.clone(),
+        });
+        current = owner;
+    }
+
+    if hops.is_empty() {
+        return Err(anyhow!("{} is not an object (no ObjectCore)", args.address));
+    }
+    crate::print_serialized(&hops)
+}
+
+/// The `ObjectCore.owner` of `address`, or `None` when it is not an object.
+fn fetch_object_owner(client: &AptosClient, address: &str) -> Result<Option<String>> {
+    let encoded = urlencoding::encode(OBJECT_CORE_TYPE);
+    match client.get_json(&format!("/accounts/{address}/resource/{encoded}")) {
+        Ok(resource) => {
+            let owner = get_nested_string(&resource, &["data", "owner"]);
+            if owner.is_empty() {
+                return Err(anyhow!("ObjectCore of {address} has no `owner` field"));
+            }
+            Ok(Some(owner))
+        }
+        Err(AptosError::NotFound(_)) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn run_account_find_function(client: &AptosClient, args: &FindFunctionArgs) -> Result<()> {
+    let modules = fetch_account_modules(client, &args.address, args.ledger_version)?;
+    let matches = find_functions(&modules, &args.function_name, args.public_only);
+    if matches.is_empty() {
+        let scope = if args.public_only { "public " } else { "" };
+        return Err(anyhow!(
+            "none of the {} module(s) at {} expose a {scope}function named `{}`",
+            modules.len(),
+            args.address,
+            args.function_name
+        ));
+    }
+    crate::print_serialized(&matches)
+}
+
+/// Functions named `name` in the modules' `abi.exposed_functions`, in module order.
+fn find_functions(modules: &[Value], name: &str, public_only: bool) -> Vec<FunctionMatch> {
+    let mut matches = Vec::new();
+    for abi in modules.iter().filter_map(|module| module.get("abi")) {
+        let module = format!(
+            "{}::{}",
+            get_nested_string(abi, &["address"]),
+            get_nested_string(abi, &["name"])
+        );
+        let functions = abi
+            .get("exposed_functions")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten();
+        for function in functions {
+            if function.get("name").and_then(Value::as_str) != Some(name) {
+                continue;
+            }
+            let visibility = get_nested_string(function, &["visibility"]);
+            if public_only && visibility != "public" {
+                continue;
+            }
+            let field = |key: &str| function.get(key).cloned().unwrap_or(Value::Null);
+            matches.push(FunctionMatch {
+                module: module.clone(),
+                name: name.to_owned(),
+                visibility,
+                is_entry: field("is_entry").as_bool().unwrap_or(false),
+                is_view: field("is_view").as_bool().unwrap_or(false),
+                generic_type_params: field("generic_type_params"),
+                params: field("params"),
+                returns: field("return"),
+            });
+        }
+    }
+    matches
+}
+
 fn run_account_source_code(client: &AptosClient, args: &SourceCodeArgs) -> Result<()> {
     let resource_type = urlencoding::encode(PACKAGE_REGISTRY_TYPE);
     let path = with_optional_ledger_version(
+        client,
         &format!("/accounts/{}/resource/{resource_type}", args.address),
         args.ledger_version,
-    );
+    )?;
 
     let resource = match client.get_json(&path) {
         Ok(data) => data,
-        Err(err) => {
-            let message = err.to_string();
-            if message.contains("resource_not_found") || message.contains("status 404") {
-                return Err(anyhow!(
-                    "no code metadata found at address; use `aptly decompile address {}`",
-                    args.address
-                ));
-            }
-            return Err(err);
+        Err(AptosError::NotFound(_)) => {
+            return Err(anyhow!(
+                "no code metadata found at address; use `aptly decompile address {}`",
+                args.address
+            ));
         }
+        Err(err) => return Err(err.into()),
     };
 
     let package_filter = args.package_name.as_deref();
@@ -283,6 +1214,7 @@ fn run_account_source_code(client: &AptosClient, args: &SourceCodeArgs) -> Resul
         .ok_or_else(|| anyhow!("failed to parse package registry resource"))?;
 
     let mut sources = Vec::new();
+    let mut listing = Vec::new();
     let mut module_exists = false;
 
     for package in packages {
@@ -316,6 +1248,19 @@ fn run_account_source_code(client: &AptosClient, args: &SourceCodeArgs) -> Resul
                 }
             }
 
+            if args.list {
+                let source_hex = module
+                    .get("source")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default();
+                listing.push(ModuleSourceListing {
+                    package: package_name.clone(),
+                    module: module_name,
+                    has_source: !source_hex.trim_start_matches("0x").is_empty(),
+                });
+                continue;
+            }
+
             let Some(source_hex) = module.get("source").and_then(Value::as_str) else {
                 continue;
             };
@@ -333,6 +1278,13 @@ fn run_account_source_code(client: &AptosClient, args: &SourceCodeArgs) -> Resul
         }
     }
 
+    if args.list {
+        if let Some(module_name) = module_filter.filter(|_| !module_exists) {
+            return Err(anyhow!("module {module_name:?} not found"));
+        }
+        return crate::print_serialized(&listing);
+    }
+
     if sources.is_empty() {
         if let Some(module_name) = module_filter {
             if module_exists {
@@ -357,7 +1309,7 @@ fn run_account_source_code(client: &AptosClient, args: &SourceCodeArgs) -> Resul
                 sources.len()
             ));
         }
-        print!("{}", sources[0].source);
+        write!(crate::pager::stdout(), "{}", sources[0].source)?;
         return Ok(());
     }
 
@@ -376,6 +1328,58 @@ fn decode_source(hex_source: &str) -> Result<String> {
 }
 
 fn run_account_sends(client: &AptosClient, args: &SendsArgs) -> Result<()> {
+    let resolver = AssetResolver::new(client);
+    let mut transfers = Vec::new();
+    if args.direction != SendsDirection::In {
+        transfers.extend(outgoing_transfers(client, args, &resolver)?);
+    }
+    if args.direction != SendsDirection::Out {
+        transfers.extend(incoming_transfers(client, args, &resolver)?);
+    }
+    if args.direction == SendsDirection::Both {
+        transfers.sort_by_key(|transfer| transfer.version);
+    }
+
+    // `format_amount` passes unparseable amounts through verbatim.
+    if let Some(transfer) = transfers
+        .iter()
+        .find(|transfer| args.strict && !is_decimal_amount(&transfer.amount))
+    {
+        return Err(anyhow!(
+            "transaction {}: transfer amount {:?} is not an integer",
+            transfer.version,
+            transfer.amount
+        ));
+    }
+
+    if args.label {
+        let (labels, _) = load_labels(client, LabelsCachePolicy::default())?;
+        let labels: HashMap<String, String> = labels
+            .into_iter()
+            .map(|(address, label)| (normalize_hex_address(&address), label))
+            .collect();
+        let lookup = |address: &str| labels.get(&normalize_hex_address(address)).cloned();
+        for transfer in &mut transfers {
+            transfer.labels = Some(TransferLabels {
+                from_label: lookup(&transfer.from),
+                to_label: lookup(&transfer.to),
+            });
+        }
+    }
+
+    if args.pretty {
+        return print_pretty_sends(&transfers, &args.columns);
+    }
+
+    crate::print_serialized(&transfers)
+}
+
+/// Transfers the account signed, read from its transaction payloads.
+fn outgoing_transfers(
+    client: &AptosClient,
+    args: &SendsArgs,
+    resolver: &AssetResolver,
+) -> Result<Vec<Transfer>> {
     let path = format!(
         "/accounts/{}/transactions?limit={}",
         args.address, args.limit
@@ -385,27 +1389,97 @@ fn run_account_sends(client: &AptosClient, args: &SendsArgs) -> Result<()> {
         .as_array()
         .ok_or_else(|| anyhow!("unexpected transactions response format"))?;
 
-    let mut metadata_cache: HashMap<String, AssetMetadata> = HashMap::new();
-    let mut transfers = Vec::new();
+    // Metadata lookups are blocking round trips, so resolve transactions on a
+    // small worker pool.
+    Ok(parallel_map(tx_array, client.concurrency(), |tx| {
+        extract_transfer(tx, resolver, args.precision)
+    })
+    .into_iter()
+    .flatten()
+    .collect())
+}
+
+/// Transfers into the account. The node lists only the transactions an
+/// account signed, so candidates come from the deposit event handles of its
+/// `CoinStore`s; every deposit to the account in those transactions is then
+/// picked up, fungible asset deposits included. Transfers that only emit
+/// fungible asset module events (no `CoinStore` deposit) are not found.
+fn incoming_transfers(
+    client: &AptosClient,
+    args: &SendsArgs,
+    resolver: &AssetResolver,
+) -> Result<Vec<Transfer>> {
+    let resources = client.get_json(&format!("/accounts/{}/resources", args.address))?;
+    let resources = resources
+        .as_array()
+        .ok_or_else(|| anyhow!("unexpected resources response format"))?;
 
-    for tx in tx_array {
-        if let Some(transfer) = extract_transfer(client, tx, &mut metadata_cache) {
-            transfers.push(transfer);
+    let mut versions = BTreeSet::new();
+    for resource in resources {
+        let resource_type = get_nested_string(resource, &["type"]);
+        if !resource_type.starts_with("0x1::coin::CoinStore<") {
+            continue;
         }
+        let counter = get_nested_string(resource, &["data", "deposit_events", "counter"])
+            .parse::<u64>()
+            .unwrap_or(0);
+        if counter == 0 {
+            continue;
+        }
+        let path = format!(
+            "/accounts/{}/events/{}/deposit_events?start={}&limit={}",
+            args.address,
+            urlencoding::encode(&resource_type),
+            counter.saturating_sub(args.limit),
+            args.limit
+        );
+        let events = client.get_json(&path)?;
+        versions.extend(
+            events
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|event| parse_u64(event.get("version")?)),
+        );
     }
 
-    if args.pretty {
-        print_pretty_sends(&transfers);
-        return Ok(());
-    }
+    let skip = versions.len().saturating_sub(args.limit as usize);
+    let versions: Vec<u64> = versions.into_iter().skip(skip).collect();
+    let paths: Vec<String> = versions
+        .iter()
+        .map(|version| format!("/transactions/by_version/{version}"))
+        .collect();
+    let account = normalize_hex_address(&args.address);
 
-    crate::print_serialized(&transfers)
+    let mut transfers = Vec::new();
+    for (version, tx) in versions.into_iter().zip(client.get_json_batch(&paths)) {
+        let tx = tx.with_context(|| format!("failed to fetch transaction {version}"))?;
+        let sender = get_nested_string(&tx, &["sender"]);
+        // The account's own transactions belong to `out`.
+        if normalize_hex_address(&sender) == account {
+            continue;
+        }
+        let hash = get_nested_string(&tx, &["hash"]);
+        for (asset, amount) in deposits_to(client, &tx, &args.address)? {
+            let metadata = resolver.resolve(&asset);
+            transfers.push(Transfer {
+                from: sender.clone(),
+                to: args.address.clone(),
+                amount: format_amount(&amount, metadata.decimals, args.precision),
+                asset: metadata.symbol,
+                version,
+                hash: hash.clone(),
+                labels: None,
+            });
+        }
+    }
+    Ok(transfers)
 }
 
 fn extract_transfer(
-    client: &AptosClient,
     tx: &Value,
-    metadata_cache: &mut HashMap<String, AssetMetadata>,
+    resolver: &AssetResolver,
+    precision: Option<u8>,
 ) -> Option<Transfer> {
     if tx.get("type")?.as_str()? != "user_transaction" {
         return None;
@@ -459,157 +1533,192 @@ fn extract_transfer(
         return None;
     }
 
-    let metadata = get_asset_metadata(client, metadata_cache, &asset, is_fungible_asset);
+    let metadata = if is_fungible_asset {
+        resolver.fungible_asset(&asset)
+    } else {
+        resolver.coin(&asset)
+    };
     let sender = tx
         .get("sender")
         .and_then(Value::as_str)
         .unwrap_or_default()
         .to_owned();
     let version = parse_u64(tx.get("version").unwrap_or(&Value::Null)).unwrap_or(0);
+    let hash = get_nested_string(tx, &["hash"]);
 
     Some(Transfer {
         from: sender,
         to,
-        amount: format_amount(&amount_str, metadata.decimals),
+        amount: format_amount(&amount_str, metadata.decimals, precision),
         asset: metadata.symbol,
         version,
+        hash,
+        labels: None,
     })
 }
 
-fn get_asset_metadata(
-    client: &AptosClient,
-    cache: &mut HashMap<String, AssetMetadata>,
-    asset: &str,
-    is_fungible_asset: bool,
-) -> AssetMetadata {
-    if let Some(cached) = cache.get(asset) {
-        return cached.clone();
-    }
-
-    let metadata = if is_fungible_asset {
-        query_fungible_asset_metadata(client, asset)
-    } else {
-        query_coin_metadata(client, asset)
-    };
-    cache.insert(asset.to_owned(), metadata.clone());
-    metadata
+fn is_decimal_amount(amount: &str) -> bool {
+    let (int_part, frac_part) = amount.split_once('.').unwrap_or((amount, ""));
+    let int_part = int_part.strip_prefix('-').unwrap_or(int_part);
+    !int_part.is_empty()
+        && int_part.bytes().all(|byte| byte.is_ascii_digit())
+        && frac_part.bytes().all(|byte| byte.is_ascii_digit())
 }
 
-fn query_fungible_asset_metadata(client: &AptosClient, metadata_addr: &str) -> AssetMetadata {
-    let mut metadata = AssetMetadata {
-        symbol: shorten_addr(metadata_addr),
-        decimals: 0,
-    };
-
-    let encoded_resource = urlencoding::encode(FUNGIBLE_METADATA_TYPE);
-    let path = format!("/accounts/{metadata_addr}/resource/{encoded_resource}");
-
-    if let Ok(resource) = client.get_json(&path) {
-        let symbol = get_nested_string(&resource, &["data", "symbol"]);
-        if !symbol.is_empty() {
-            metadata.symbol = symbol;
-        }
-
-        if let Some(decimals) = parse_u64(
-            resource
-                .get("data")
-                .and_then(|d| d.get("decimals"))
-                .unwrap_or(&Value::Null),
-        ) {
-            metadata.decimals = decimals as u8;
-        }
+fn print_pretty_sends(transfers: &[Transfer], columns: &[SendsColumn]) -> Result<()> {
+    let rows: Vec<Vec<String>> = transfers
+        .iter()
+        .map(|transfer| {
+            columns
+                .iter()
+                .map(|column| sends_cell(transfer, *column))
+                .collect()
+        })
+        .collect();
+    let widths: Vec<usize> = (0..columns.len())
+        .map(|index| {
+            rows.iter()
+                .map(|row| row[index].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let mut out = crate::pager::stdout();
+
+    for row in rows {
+        let last = row.len().saturating_sub(1);
+        let line = row
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| {
+                let width = widths[index];
+                match columns[index] {
+                    SendsColumn::Amount => format!("{cell:>width$}"),
+                    _ if index == last => cell.clone(),
+                    _ => format!("{cell:<width$}"),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(out, "{line}")?;
     }
-
-    metadata
+    Ok(())
 }
 
-fn query_coin_metadata(client: &AptosClient, coin_type: &str) -> AssetMetadata {
-    if coin_type == "0x1::aptos_coin::AptosCoin" {
-        return AssetMetadata {
-            symbol: "APT".to_owned(),
-            decimals: 8,
-        };
-    }
-
-    let mut metadata = AssetMetadata {
-        symbol: shorten_addr(coin_type),
-        decimals: 0,
-    };
-
-    let Some(issuer) = coin_type.split("::").next() else {
-        return metadata;
+fn sends_cell(transfer: &Transfer, column: SendsColumn) -> String {
+    let labelled = |address: &str, label: Option<&String>| match label {
+        Some(label) => format!("{label} ({})", shorten_addr(address)),
+        None => address.to_owned(),
     };
-    if issuer.is_empty() {
-        return metadata;
-    }
-
-    let resource_type = format!("0x1::coin::CoinInfo<{coin_type}>");
-    let encoded_resource = urlencoding::encode(&resource_type);
-    let path = format!("/accounts/{issuer}/resource/{encoded_resource}");
-
-    if let Ok(resource) = client.get_json(&path) {
-        let symbol = get_nested_string(&resource, &["data", "symbol"]);
-        if !symbol.is_empty() {
-            metadata.symbol = symbol;
-        }
-
-        if let Some(decimals) = parse_u64(
-            resource
-                .get("data")
-                .and_then(|d| d.get("decimals"))
-                .unwrap_or(&Value::Null),
-        ) {
-            metadata.decimals = decimals as u8;
-        }
+    let labels = transfer.labels.as_ref();
+    match column {
+        SendsColumn::Version => format!("[{}]", transfer.version),
+        SendsColumn::Amount => transfer.amount.clone(),
+        SendsColumn::Asset => transfer.asset.clone(),
+        SendsColumn::From => labelled(
+            &transfer.from,
+            labels.and_then(|labels| labels.from_label.as_ref()),
+        ),
+        SendsColumn::To => format!(
+            "→ {}",
+            labelled(
+                &transfer.to,
+                labels.and_then(|labels| labels.to_label.as_ref())
+            )
+        ),
+        SendsColumn::Hash => shorten_addr(&transfer.hash),
     }
-
-    metadata
 }
 
-fn format_amount(amount: &str, decimals: u8) -> String {
-    if decimals == 0 {
-        return amount.to_owned();
+fn get_inner_or_string(value: &Value) -> String {
+    if let Some(inner) = value.get("inner").and_then(Value::as_str) {
+        return inner.to_owned();
     }
+    value_to_string(value)
+}
 
-    let Ok(raw) = BigInt::from_str(amount) else {
-        return amount.to_owned();
-    };
-
-    let divisor = BigInt::from(10u8).pow(decimals as u32);
-    let int_part = &raw / &divisor;
-    let frac_part = &raw % &divisor;
-    let mut frac_str = format!("{:0width$}", frac_part, width = decimals as usize);
-    while frac_str.ends_with('0') {
-        frac_str.pop();
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_resource_type_without_touching_the_resource() {
+        let resource = serde_json::json!({
+            "type": "0x1::coin::CoinInfo<0x1::aptos_coin::AptosCoin>",
+            "data": {"decimals": 8}
+        });
+        assert_eq!(
+            pretty_resource_type(&resource).unwrap(),
+            "0x1::coin::CoinInfo<\n  0x1::aptos_coin::AptosCoin\n>"
+        );
+        assert!(pretty_resource_type(&serde_json::json!({"data": {}})).is_err());
     }
 
-    if frac_str.is_empty() {
-        int_part.to_string()
-    } else {
-        format!("{int_part}.{frac_str}")
+    #[test]
+    fn diffs_nested_resource_fields() {
+        let mut diff = ResourceDiff {
+            address: "0x1".to_owned(),
+            resource_type: "0x1::m::R".to_owned(),
+            from: 1,
+            to: 2,
+            exists_at_from: true,
+            exists_at_to: true,
+            note: None,
+            added: BTreeMap::new(),
+            removed: BTreeMap::new(),
+            changed: BTreeMap::new(),
+        };
+        let before = serde_json::json!({"coin": {"value": "1"}, "frozen": false, "old": 1});
+        let after = serde_json::json!({"coin": {"value": "2"}, "frozen": false, "new": [1]});
+        diff_values("", &before, &after, &mut diff);
+
+        assert_eq!(diff.added.get("new"), Some(&serde_json::json!([1])));
+        assert_eq!(diff.removed.get("old"), Some(&serde_json::json!(1)));
+        assert_eq!(
+            diff.changed.get("coin.value"),
+            Some(&ValueChange {
+                before: serde_json::json!("1"),
+                after: serde_json::json!("2"),
+            })
+        );
+        assert_eq!(diff.changed.len(), 1);
     }
-}
 
-fn print_pretty_sends(transfers: &[Transfer]) {
-    let max_amount_len = transfers.iter().map(|t| t.amount.len()).max().unwrap_or(0);
-    let max_asset_len = transfers.iter().map(|t| t.asset.len()).max().unwrap_or(0);
-
-    for transfer in transfers {
-        println!(
-            "[{}] {:>amount_width$} {:<asset_width$} → {}",
-            transfer.version,
-            transfer.amount,
-            transfer.asset,
-            transfer.to,
-            amount_width = max_amount_len,
-            asset_width = max_asset_len
-        );
+    #[test]
+    fn recognizes_decimal_amounts() {
+        for amount in ["0", "100", "-5", "1.25", "-0.5", "3."] {
+            assert!(is_decimal_amount(amount), "{amount}");
+        }
+        for amount in ["", "-", ".5", "1e3", "0x10", "1.2.3", " 1", "NaN"] {
+            assert!(!is_decimal_amount(amount), "{amount}");
+        }
     }
-}
 
-fn get_inner_or_string(value: &Value) -> String {
-    if let Some(inner) = value.get("inner").and_then(Value::as_str) {
-        return inner.to_owned();
+    #[test]
+    fn finds_functions_by_name_and_visibility() {
+        let function = |name: &str, visibility: &str| {
+            serde_json::json!({
+                "name": name, "visibility": visibility, "is_entry": false, "is_view": false,
+                "generic_type_params": [], "params": ["address"], "return": ["u64"]
+            })
+        };
+        let modules = vec![
+            serde_json::json!({"abi": {"address": "0x1", "name": "coin", "exposed_functions": [
+                function("balance", "public"), function("value", "public")
+            ]}}),
+            serde_json::json!({"abi": {"address": "0x1", "name": "vault", "exposed_functions": [
+                function("balance", "friend")
+            ]}}),
+        ];
+
+        let all = find_functions(&modules, "balance", false);
+        let found: Vec<_> = all.iter().map(|m| m.module.as_str()).collect();
+        assert_eq!(found, ["0x1::coin", "0x1::vault"]);
+        assert_eq!(all[0].returns, serde_json::json!(["u64"]));
+
+        let public = find_functions(&modules, "balance", true);
+        assert_eq!(public.len(), 1);
+        assert_eq!(public[0].module, "0x1::coin");
+        assert!(find_functions(&modules, "missing", false).is_empty());
     }
-    value_to_string(value)
 }
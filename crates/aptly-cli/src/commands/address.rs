@@ -1,39 +1,457 @@
-use anyhow::{anyhow, Context, Result};
-use clap::Args;
+use anyhow::{anyhow, bail, Context, Result};
+use aptly_aptos::{send_with_retries, AptosClient};
+use clap::{Args, Subcommand};
+use reqwest::blocking::Client;
 use reqwest::StatusCode;
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-const LABELS_URL: &str =
-    "https://raw.githubusercontent.com/ThalaLabs/aptos-labels/main/mainnet.json";
+use crate::commands::common::normalize_hex_address;
+use crate::config::config_dir;
+use crate::output::OutputFormat;
+
+/// Label sources tried in order; later entries mirror the same file.
+const LABELS_URLS: &[&str] = &[
+    "https://raw.githubusercontent.com/ThalaLabs/aptos-labels/main/mainnet.json",
+    "https://cdn.jsdelivr.net/gh/ThalaLabs/aptos-labels@main/mainnet.json",
+];
+const LABELS_CACHE_FILE: &str = "labels-mainnet.json";
+const DEFAULT_LABELS_CACHE_TTL_SECS: u64 = 24 * 60 * 60;
+/// User-maintained labels under the config dir, merged over the remote ones.
+const LOCAL_LABELS_FILE: &str = "labels.json";
+
+/// How [`fetch_labels`] uses the on-disk label cache.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct LabelsCachePolicy {
+    /// Refetch once the cached copy is older than this.
+    pub(crate) ttl: Duration,
+    /// Only read the cache; never touch the network.
+    pub(crate) offline: bool,
+    /// Ignore the cache and redownload.
+    pub(crate) refresh: bool,
+}
+
+impl Default for LabelsCachePolicy {
+    fn default() -> Self {
+        Self {
+            ttl: Duration::from_secs(DEFAULT_LABELS_CACHE_TTL_SECS),
+            offline: false,
+            refresh: false,
+        }
+    }
+}
 
 #[derive(Args)]
-#[command(after_help = "Examples:\n  aptly address thala\n  aptly address panora")]
+#[command(
+    after_help = "Examples:\n  aptly address thala\n  aptly address panora\n  aptly address thala --explain\n  aptly address pool --limit 10\n  aptly address \"Thala Router\" --exact\n  aptly address thala --offline\n  aptly address thala --refresh\n  aptly address --by-address 0x1\n  aptly address add 0xcafe \"My Vault\"\n  aptly address remove 0xcafe"
+)]
+#[command(args_conflicts_with_subcommands = true)]
 pub(crate) struct AddressCommand {
+    #[command(subcommand)]
+    pub(crate) command: Option<AddressSubcommand>,
     /// Case-insensitive substring to match against known labels.
-    #[arg(value_name = "QUERY")]
-    pub(crate) query: String,
+    #[arg(value_name = "QUERY", required_unless_present = "by_address")]
+    pub(crate) query: Option<String>,
+    /// Look up the label of one address (short or long form) instead of searching labels.
+    #[arg(long, value_name = "ADDRESS", conflicts_with_all = ["query", "exact", "limit"])]
+    pub(crate) by_address: Option<String>,
+    /// Only match labels equal to the query (case-insensitive).
+    #[arg(long, default_value_t = false)]
+    pub(crate) exact: bool,
+    /// Return at most N matches, ordered by address.
+    #[arg(long, value_name = "N")]
+    pub(crate) limit: Option<usize>,
+    /// Print which label source was used to stderr.
+    #[arg(long, default_value_t = false)]
+    pub(crate) explain: bool,
+    /// Use only the cached labels; fail if there is no cached copy.
+    #[arg(long, default_value_t = false, conflicts_with = "refresh")]
+    pub(crate) offline: bool,
+    /// Redownload the labels even if the cached copy is fresh.
+    #[arg(long, default_value_t = false)]
+    pub(crate) refresh: bool,
+    /// Seconds a cached labels file stays fresh.
+    #[arg(long, value_name = "SECS", default_value_t = DEFAULT_LABELS_CACHE_TTL_SECS)]
+    pub(crate) cache_ttl_secs: u64,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum AddressSubcommand {
+    #[command(about = "Save a local label for an address, overriding any remote label")]
+    Add(LabelAddArgs),
+    #[command(about = "Delete a local label")]
+    Remove(LabelRemoveArgs),
+}
+
+#[derive(Args)]
+pub(crate) struct LabelAddArgs {
+    /// Address to label (short or long form).
+    #[arg(value_name = "ADDRESS")]
+    pub(crate) address: String,
+    /// Label text.
+    #[arg(value_name = "LABEL")]
+    pub(crate) label: String,
+}
+
+#[derive(Args)]
+pub(crate) struct LabelRemoveArgs {
+    /// Address whose local label to delete (short or long form).
+    #[arg(value_name = "ADDRESS")]
+    pub(crate) address: String,
+}
+
+pub(crate) fn run_address(client: &AptosClient, command: AddressCommand) -> Result<()> {
+    match command.command {
+        Some(AddressSubcommand::Add(args)) => return run_address_add(&args),
+        Some(AddressSubcommand::Remove(args)) => return run_address_remove(&args),
+        None => {}
+    }
+
+    let policy = LabelsCachePolicy {
+        ttl: Duration::from_secs(command.cache_ttl_secs),
+        offline: command.offline,
+        refresh: command.refresh,
+    };
+    let (labels, source) = load_labels(client, policy)?;
+    if command.explain {
+        eprintln!("labels source: {source}");
+    }
+
+    if let Some(address) = &command.by_address {
+        let (address, label) = lookup_label(&labels, address)?;
+        if label.is_none() {
+            eprintln!("no label for {address}");
+        }
+        return crate::print_pretty_json(&serde_json::json!({
+            "address": address,
+            "label": label,
+        }));
+    }
+
+    let query = command.query.unwrap_or_default();
+    let matches = search_labels(labels, &query, command.exact, command.limit);
+    if crate::output::format() == OutputFormat::Csv {
+        // One row per match, rather than a single row holding the object.
+        let rows: Vec<LabelMatch> = matches
+            .into_iter()
+            .map(|(address, label)| LabelMatch { address, label })
+            .collect();
+        return crate::print_serialized(&rows);
+    }
+    crate::print_serialized(&matches)
+}
+
+#[derive(Serialize)]
+struct LabelMatch {
+    address: String,
+    label: String,
+}
+
+/// Labels containing (or, with `exact`, equal to) `query`, case-insensitively,
+/// keyed and ordered by address and cut to `limit`.
+fn search_labels(
+    labels: HashMap<String, String>,
+    query: &str,
+    exact: bool,
+    limit: Option<usize>,
+) -> BTreeMap<String, String> {
+    let query = query.to_lowercase();
+    labels
+        .into_iter()
+        .filter(|(_, label)| {
+            let label = label.to_lowercase();
+            if exact {
+                label == query
+            } else {
+                label.contains(&query)
+            }
+        })
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .take(limit.unwrap_or(usize::MAX))
+        .collect()
+}
+
+/// Finds the label for `address`, comparing both sides in normalized long
+/// form since the labels file mixes short and zero-padded keys. Returns the
+/// address as written in the labels file when found, else the input.
+fn lookup_label(
+    labels: &HashMap<String, String>,
+    address: &str,
+) -> Result<(String, Option<String>)> {
+    let address = parse_address(address)?;
+    let wanted = normalize_hex_address(&address);
+    let found = labels
+        .iter()
+        .find(|(key, _)| normalize_hex_address(key) == wanted)
+        .map(|(key, label)| (key.clone(), Some(label.clone())));
+    Ok(found.unwrap_or((address, None)))
+}
+
+/// `address` as lowercase `0x`-prefixed hex, as typed (not zero-padded).
+fn parse_address(address: &str) -> Result<String> {
+    let hex = address.trim().trim_start_matches("0x");
+    if hex.is_empty() || hex.len() > 64 || !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        bail!("invalid address `{address}`: expected up to 64 hex digits");
+    }
+    Ok(format!("0x{}", hex.to_ascii_lowercase()))
+}
+
+fn run_address_add(args: &LabelAddArgs) -> Result<()> {
+    let address = parse_address(&args.address)?;
+    let label = args.label.trim();
+    if label.is_empty() {
+        bail!("label cannot be empty");
+    }
+    let path = local_labels_path()?;
+    let mut labels = read_local_labels(&path)?;
+    if let Some(previous) = remove_local_label(&mut labels, &address) {
+        eprintln!("replacing local label `{previous}`");
+    }
+    labels.insert(address.clone(), label.to_owned());
+    write_local_labels(&path, &labels)?;
+    crate::print_pretty_json(&serde_json::json!({ "address": address, "label": label }))
+}
+
+fn run_address_remove(args: &LabelRemoveArgs) -> Result<()> {
+    let address = parse_address(&args.address)?;
+    let path = local_labels_path()?;
+    let mut labels = read_local_labels(&path)?;
+    let Some(label) = remove_local_label(&mut labels, &address) else {
+        bail!("no local label for {address} in {}", path.display());
+    };
+    write_local_labels(&path, &labels)?;
+    crate::print_pretty_json(&serde_json::json!({ "address": address, "label": label }))
 }
 
-pub(crate) fn run_address(command: AddressCommand) -> Result<()> {
-    let response =
-        reqwest::blocking::get(LABELS_URL).context("failed to fetch address labels source")?;
+/// Removes the entry for `address` in any address form, returning its label.
+fn remove_local_label(labels: &mut BTreeMap<String, String>, address: &str) -> Option<String> {
+    let wanted = normalize_hex_address(address);
+    let key = labels
+        .keys()
+        .find(|key| normalize_hex_address(key) == wanted)
+        .cloned()?;
+    labels.remove(&key)
+}
+
+/// [`fetch_labels`] with the local labels from `address add` laid over
+/// the remote ones; a local label replaces a remote one for the same address.
+pub(crate) fn load_labels(
+    client: &AptosClient,
+    policy: LabelsCachePolicy,
+) -> Result<(HashMap<String, String>, String)> {
+    let (mut labels, mut source) = fetch_labels(client, policy)?;
+    if let Ok(path) = local_labels_path() {
+        let local = read_local_labels(&path)?;
+        if !local.is_empty() {
+            merge_local_labels(&mut labels, local);
+            source.push_str(&format!(" + local {}", path.display()));
+        }
+    }
+    Ok((labels, source))
+}
+
+fn merge_local_labels(labels: &mut HashMap<String, String>, local: BTreeMap<String, String>) {
+    let overridden: HashSet<String> = local.keys().map(|key| normalize_hex_address(key)).collect();
+    labels.retain(|address, _| !overridden.contains(&normalize_hex_address(address)));
+    labels.extend(local);
+}
+
+fn local_labels_path() -> Result<PathBuf> {
+    config_dir()
+        .map(|dir| dir.join(LOCAL_LABELS_FILE))
+        .ok_or_else(|| anyhow!("no config dir for local labels; set HOME or XDG_CONFIG_HOME"))
+}
+
+/// The local labels, or none when the file does not exist yet.
+fn read_local_labels(path: &Path) -> Result<BTreeMap<String, String>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(err) => return Err(err).with_context(|| format!("failed to read {}", path.display())),
+    };
+    serde_json::from_str(&text)
+        .with_context(|| format!("invalid local labels file {}", path.display()))
+}
+
+fn write_local_labels(path: &Path, labels: &BTreeMap<String, String>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let json = serde_json::to_string_pretty(labels)?;
+    fs::write(path, json + "\n").with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Loads the address → label map, returning it with the source used. A
+/// fresh copy under the cache dir is used as-is; otherwise the labels are
+/// downloaded and cached, falling back to a stale copy if every source fails.
+pub(crate) fn fetch_labels(
+    client: &AptosClient,
+    policy: LabelsCachePolicy,
+) -> Result<(HashMap<String, String>, String)> {
+    let cache_path = labels_cache_path();
+    let cached = match (&cache_path, policy.refresh) {
+        (Some(path), false) => read_cached_labels(path, policy.ttl),
+        _ => None,
+    };
+
+    if policy.offline {
+        let path = cache_path
+            .as_deref()
+            .ok_or_else(|| anyhow!("--offline needs a cache dir; set HOME or XDG_CACHE_HOME"))?;
+        return match cached {
+            Some((labels, _)) => Ok((labels, format!("cache {}", path.display()))),
+            None => Err(anyhow!(
+                "no cached address labels at {}; run once without --offline",
+                path.display()
+            )),
+        };
+    }
+    if let (Some(path), Some((labels, true))) = (&cache_path, &cached) {
+        return Ok((labels.clone(), format!("cache {}", path.display())));
+    }
+
+    let http = client.http_config().build_client()?;
+    let mut failures = Vec::new();
+    for url in LABELS_URLS {
+        match fetch_labels_from(&http, url, client.max_retries()) {
+            Ok(labels) => {
+                if let Some(path) = &cache_path {
+                    if let Err(err) = write_cached_labels(path, &labels) {
+                        eprintln!("warning: failed to cache address labels: {err:#}");
+                    }
+                }
+                return Ok((labels, (*url).to_owned()));
+            }
+            Err(err) => failures.push(format!("{url}: {err:#}")),
+        }
+    }
+
+    if let (Some(path), Some((labels, _))) = (&cache_path, cached) {
+        eprintln!("warning: failed to refresh address labels; using stale cache");
+        return Ok((labels, format!("stale cache {}", path.display())));
+    }
+    Err(anyhow!(
+        "failed to fetch address labels from all sources:\n  {}",
+        failures.join("\n  ")
+    ))
+}
+
+fn labels_cache_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("aptly").join(LABELS_CACHE_FILE))
+}
+
+/// The cached labels and whether they are younger than `ttl`; `None` when
+/// there is no readable cache.
+fn read_cached_labels(path: &Path, ttl: Duration) -> Option<(HashMap<String, String>, bool)> {
+    let text = fs::read_to_string(path).ok()?;
+    let labels = serde_json::from_str(&text).ok()?;
+    let fresh = fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < ttl);
+    Some((labels, fresh))
+}
+
+fn write_cached_labels(path: &Path, labels: &HashMap<String, String>) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {}", parent.display()))?;
+    }
+    let json = serde_json::to_vec(labels)?;
+    fs::write(path, json).with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Fetches one labels source, retrying transient failures the same way node
+/// requests are retried.
+fn fetch_labels_from(
+    http: &Client,
+    url: &str,
+    max_retries: u32,
+) -> Result<HashMap<String, String>> {
+    let response = send_with_retries(max_retries, || http.get(url))
+        .context("failed to fetch address labels source")?;
     let status = response.status();
     let body = response
         .text()
         .context("failed to read labels response body")?;
-
     if status != StatusCode::OK {
-        return Err(anyhow!("API error (status {}): {}", status.as_u16(), body));
+        bail!("API error (status {}): {}", status.as_u16(), body);
     }
+    serde_json::from_str(&body).context("failed to decode labels response")
+}
 
-    let labels: HashMap<String, String> =
-        serde_json::from_str(&body).context("failed to decode labels response")?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let query = command.query.to_lowercase();
-    let matches: HashMap<String, String> = labels
-        .into_iter()
-        .filter(|(_, label)| label.to_lowercase().contains(&query))
-        .collect();
+    #[test]
+    fn looks_up_labels_across_address_forms() {
+        let labels = HashMap::from([
+            ("0x1".to_owned(), "Aptos Framework".to_owned()),
+            (format!("0x{:0>64}", "cafe"), "Cafe".to_owned()),
+        ]);
+        let long_one = format!("0x{:0>64}", "1");
+        assert_eq!(
+            lookup_label(&labels, &long_one).unwrap(),
+            ("0x1".to_owned(), Some("Aptos Framework".to_owned()))
+        );
+        assert_eq!(
+            lookup_label(&labels, "0xCAFE").unwrap().1.as_deref(),
+            Some("Cafe")
+        );
+        assert_eq!(
+            lookup_label(&labels, "0xbeef").unwrap(),
+            ("0xbeef".to_owned(), None)
+        );
+        assert!(lookup_label(&labels, "0xzz").is_err());
+    }
 
-    crate::print_serialized(&matches)
+    #[test]
+    fn orders_matches_by_address_before_applying_limit() {
+        let labels = HashMap::from([
+            ("0x3".to_owned(), "Foo Router".to_owned()),
+            ("0x1".to_owned(), "Zz Foo".to_owned()),
+            ("0x2".to_owned(), "Aa Foo".to_owned()),
+            ("0x4".to_owned(), "Bar".to_owned()),
+        ]);
+        let found: Vec<_> = search_labels(labels.clone(), "foo", false, Some(2))
+            .into_keys()
+            .collect();
+        assert_eq!(found, ["0x1", "0x2"]);
+
+        assert_eq!(
+            search_labels(labels, "BAR", true, None),
+            BTreeMap::from([("0x4".to_owned(), "Bar".to_owned())])
+        );
+    }
+
+    #[test]
+    fn local_labels_override_remote_across_address_forms() {
+        let mut labels = HashMap::from([
+            (format!("0x{:0>64}", "cafe"), "Remote Cafe".to_owned()),
+            ("0xbeef".to_owned(), "Beef".to_owned()),
+        ]);
+        let local = BTreeMap::from([
+            ("0xcafe".to_owned(), "My Vault".to_owned()),
+            ("0xf00d".to_owned(), "Food".to_owned()),
+        ]);
+        merge_local_labels(&mut labels, local);
+
+        assert_eq!(labels.len(), 3);
+        assert_eq!(labels["0xcafe"], "My Vault");
+        assert_eq!(labels["0xbeef"], "Beef");
+        assert_eq!(labels["0xf00d"], "Food");
+    }
 }
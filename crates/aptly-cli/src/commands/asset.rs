@@ -0,0 +1,263 @@
+use aptly_aptos::AptosClient;
+use num_bigint::BigInt;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::commands::common::{get_nested_string, is_apt_fa_address, parse_u64, shorten_addr};
+
+const FUNGIBLE_METADATA_TYPE: &str = "0x1::fungible_asset::Metadata";
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AssetMetadata {
+    pub(crate) symbol: String,
+    pub(crate) decimals: u8,
+}
+
+/// Resolves asset symbols and decimals through the node, querying each
+/// asset once even when several workers ask for it at the same time. Assets
+/// that cannot be resolved fall back to a shortened name and 0 decimals.
+pub(crate) struct AssetResolver<'a> {
+    client: &'a AptosClient,
+    cache: Mutex<HashMap<String, Arc<OnceLock<AssetMetadata>>>>,
+}
+
+impl<'a> AssetResolver<'a> {
+    pub(crate) fn new(client: &'a AptosClient) -> Self {
+        Self {
+            client,
+            cache: Mutex::default(),
+        }
+    }
+
+    /// Metadata of a fungible asset, by its metadata object address.
+    pub(crate) fn fungible_asset(&self, metadata_addr: &str) -> AssetMetadata {
+        self.cached(metadata_addr, || {
+            query_fungible_asset_metadata(self.client, metadata_addr)
+        })
+    }
+
+    /// Metadata of a legacy coin, by its coin type.
+    pub(crate) fn coin(&self, coin_type: &str) -> AssetMetadata {
+        self.cached(coin_type, || query_coin_metadata(self.client, coin_type))
+    }
+
+    /// Either kind: coin types name a struct (`0x1::aptos_coin::AptosCoin`),
+    /// fungible assets are bare metadata addresses.
+    pub(crate) fn resolve(&self, asset: &str) -> AssetMetadata {
+        if asset.contains("::") {
+            self.coin(asset)
+        } else {
+            self.fungible_asset(asset)
+        }
+    }
+
+    fn cached(&self, asset: &str, query: impl FnOnce() -> AssetMetadata) -> AssetMetadata {
+        let entry = self
+            .cache
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(asset.to_owned())
+            .or_default()
+            .clone();
+        entry.get_or_init(query).clone()
+    }
+}
+
+fn query_fungible_asset_metadata(client: &AptosClient, metadata_addr: &str) -> AssetMetadata {
+    if is_apt_fa_address(metadata_addr) {
+        return AssetMetadata {
+            symbol: "APT".to_owned(),
+            decimals: 8,
+        };
+    }
+
+    let mut metadata = AssetMetadata {
+        symbol: shorten_addr(metadata_addr),
+        decimals: 0,
+    };
+
+    let encoded_resource = urlencoding::encode(FUNGIBLE_METADATA_TYPE);
+    let path = format!("/accounts/{metadata_addr}/resource/{encoded_resource}");
+
+    if let Ok(resource) = client.get_json_cached(&path) {
+        let symbol = get_nested_string(&resource, &["data", "symbol"]);
+        if !symbol.is_empty() {
+            metadata.symbol = symbol;
+        }
+
+        if let Some(decimals) = parse_u64(
+            resource
+                .get("data")
+                .and_then(|d| d.get("decimals"))
+                .unwrap_or(&Value::Null),
+        ) {
+            metadata.decimals = decimals as u8;
+        }
+    }
+
+    metadata
+}
+
+fn query_coin_metadata(client: &AptosClient, coin_type: &str) -> AssetMetadata {
+    if coin_type == "0x1::aptos_coin::AptosCoin" {
+        return AssetMetadata {
+            symbol: "APT".to_owned(),
+            decimals: 8,
+        };
+    }
+
+    let mut metadata = AssetMetadata {
+        symbol: shorten_addr(coin_type),
+        decimals: 0,
+    };
+
+    let Some(issuer) = coin_type.split("::").next() else {
+        return metadata;
+    };
+    if issuer.is_empty() {
+        return metadata;
+    }
+
+    let resource_type = format!("0x1::coin::CoinInfo<{coin_type}>");
+    let encoded_resource = urlencoding::encode(&resource_type);
+    let path = format!("/accounts/{issuer}/resource/{encoded_resource}");
+
+    if let Ok(resource) = client.get_json_cached(&path) {
+        let symbol = get_nested_string(&resource, &["data", "symbol"]);
+        if !symbol.is_empty() {
+            metadata.symbol = symbol;
+        }
+
+        if let Some(decimals) = parse_u64(
+            resource
+                .get("data")
+                .and_then(|d| d.get("decimals"))
+                .unwrap_or(&Value::Null),
+        ) {
+            metadata.decimals = decimals as u8;
+        }
+    }
+
+    metadata
+}
+
+/// Renders a raw integer amount with `decimals` implied fractional digits,
+/// stripping trailing zeros. `max_precision` rounds half-up to at most that
+/// many fractional digits; `None` keeps full precision.
+pub(crate) fn format_amount(amount: &str, decimals: u8, max_precision: Option<u8>) -> String {
+    if decimals == 0 {
+        return amount.to_owned();
+    }
+    // Format the magnitude so rounding and the fractional part stay positive.
+    if let Some(magnitude) = amount.strip_prefix('-') {
+        if !magnitude.bytes().all(|byte| byte.is_ascii_digit()) {
+            return amount.to_owned();
+        }
+        return match format_amount(magnitude, decimals, max_precision) {
+            zero if zero == "0" => zero,
+            formatted => format!("-{formatted}"),
+        };
+    }
+
+    let Ok(mut raw) = BigInt::from_str(amount) else {
+        return amount.to_owned();
+    };
+
+    let mut decimals = decimals;
+    if let Some(precision) = max_precision.filter(|precision| *precision < decimals) {
+        let step = BigInt::from(10u8).pow((decimals - precision) as u32);
+        raw = (raw + &step / 2) / &step;
+        decimals = precision;
+        if decimals == 0 {
+            return raw.to_string();
+        }
+    }
+
+    let divisor = BigInt::from(10u8).pow(decimals as u32);
+    let int_part = &raw / &divisor;
+    let frac_part = &raw % &divisor;
+    let mut frac_str = format!("{:0width$}", frac_part, width = decimals as usize);
+    while frac_str.ends_with('0') {
+        frac_str.pop();
+    }
+
+    if frac_str.is_empty() {
+        int_part.to_string()
+    } else {
+        format!("{int_part}.{frac_str}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_apt_without_the_node_and_falls_back_for_unknown_assets() {
+        // Nothing listens on port 1, so every lookup that reaches the node fails.
+        let client = AptosClient::new("http://127.0.0.1:1")
+            .unwrap()
+            .with_retries(0);
+        let resolver = AssetResolver::new(&client);
+
+        for apt in [
+            "0x1::aptos_coin::AptosCoin",
+            "0xa",
+            &format!("0x{:0>64}", "a"),
+        ] {
+            let metadata = resolver.resolve(apt);
+            assert_eq!((metadata.symbol.as_str(), metadata.decimals), ("APT", 8));
+        }
+
+        let fa = format!("0x{}", "b".repeat(64));
+        let metadata = resolver.resolve(&fa);
+        assert_eq!(metadata.symbol, shorten_addr(&fa));
+        assert_eq!(metadata.decimals, 0);
+
+        let coin = "0xcafe::coin::Unknown";
+        let metadata = resolver.resolve(coin);
+        assert_eq!(metadata.symbol, shorten_addr(coin));
+        assert_eq!(metadata.decimals, 0);
+    }
+
+    #[test]
+    fn formats_full_precision_by_default() {
+        assert_eq!(format_amount("123456789", 8, None), "1.23456789");
+        assert_eq!(format_amount("100000000", 8, None), "1");
+        assert_eq!(format_amount("120000000", 8, None), "1.2");
+        assert_eq!(format_amount("42", 0, None), "42");
+        assert_eq!(format_amount("not-a-number", 8, None), "not-a-number");
+    }
+
+    #[test]
+    fn rounds_half_up_at_precision_boundary() {
+        assert_eq!(format_amount("123450000", 8, Some(4)), "1.2345");
+        assert_eq!(format_amount("123455000", 8, Some(4)), "1.2346");
+        assert_eq!(format_amount("123454999", 8, Some(4)), "1.2345");
+        assert_eq!(format_amount("199999999", 8, Some(2)), "2");
+        assert_eq!(format_amount("150000000", 8, Some(0)), "2");
+        assert_eq!(format_amount("149999999", 8, Some(0)), "1");
+    }
+
+    #[test]
+    fn strips_trailing_zeros_after_capping() {
+        assert_eq!(format_amount("120000001", 8, Some(3)), "1.2");
+        assert_eq!(format_amount("100000001", 8, Some(6)), "1");
+        assert_eq!(format_amount("123", 8, Some(2)), "0");
+    }
+
+    #[test]
+    fn formats_negative_amounts() {
+        assert_eq!(format_amount("-150000000", 8, None), "-1.5");
+        assert_eq!(format_amount("-5", 8, None), "-0.00000005");
+        assert_eq!(format_amount("-5", 8, Some(2)), "0");
+        assert_eq!(format_amount("-x", 8, None), "-x");
+    }
+
+    #[test]
+    fn ignores_precision_above_decimals() {
+        assert_eq!(format_amount("123456789", 8, Some(12)), "1.23456789");
+    }
+}
@@ -1,10 +1,11 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use aptly_aptos::AptosClient;
 use clap::{Args, Subcommand};
+use serde_json::Value;
 
 #[derive(Args)]
 #[command(
-    after_help = "Examples:\n  aptly block 1000\n  aptly block 1000 --with-transactions\n  aptly block by-version 4300326632"
+    after_help = "Examples:\n  aptly block 1000\n  aptly block 1000 --with-transactions\n  aptly block by-version 4300326632\n  aptly block range 1000 1010 --with-transactions"
 )]
 pub(crate) struct BlockCommand {
     #[command(subcommand)]
@@ -21,6 +22,8 @@ pub(crate) struct BlockCommand {
 pub(crate) enum BlockSubcommand {
     #[command(name = "by-version", about = "Fetch block by ledger version")]
     ByVersion(ByVersionArgs),
+    #[command(about = "Fetch an inclusive range of blocks by height as a JSON array")]
+    Range(RangeArgs),
 }
 
 #[derive(Args)]
@@ -33,6 +36,22 @@ pub(crate) struct ByVersionArgs {
     pub(crate) with_transactions: bool,
 }
 
+#[derive(Args)]
+pub(crate) struct RangeArgs {
+    /// First block height, inclusive.
+    #[arg(value_name = "START_HEIGHT")]
+    pub(crate) start_height: u64,
+    /// Last block height, inclusive.
+    #[arg(value_name = "END_HEIGHT")]
+    pub(crate) end_height: u64,
+    /// Include full transaction payloads in block responses.
+    #[arg(long, default_value_t = false)]
+    pub(crate) with_transactions: bool,
+    /// Refuse ranges spanning more blocks than this.
+    #[arg(long, default_value_t = 100)]
+    pub(crate) max_blocks: u64,
+}
+
 pub(crate) fn run_block(client: &AptosClient, command: BlockCommand) -> Result<()> {
     match command.command {
         Some(BlockSubcommand::ByVersion(args)) => {
@@ -43,16 +62,71 @@ pub(crate) fn run_block(client: &AptosClient, command: BlockCommand) -> Result<(
             let value = client.get_json(&path)?;
             crate::print_pretty_json(&value)
         }
+        Some(BlockSubcommand::Range(args)) => run_block_range(client, &args),
         None => {
             let height = command
                 .height
                 .ok_or_else(|| anyhow!("missing block height or subcommand"))?;
-            let path = format!(
-                "/blocks/by_height/{height}?with_transactions={}",
-                command.with_transactions
-            );
-            let value = client.get_json(&path)?;
+            let value = client.get_json(&by_height_path(&height, command.with_transactions))?;
             crate::print_pretty_json(&value)
         }
     }
 }
+
+fn run_block_range(client: &AptosClient, args: &RangeArgs) -> Result<()> {
+    check_range(args.start_height, args.end_height, args.max_blocks)?;
+
+    let heights = args.start_height..=args.end_height;
+    let paths: Vec<String> = heights
+        .clone()
+        .map(|height| by_height_path(&height.to_string(), args.with_transactions))
+        .collect();
+    let blocks = heights
+        .zip(client.get_json_batch(&paths))
+        .map(|(height, block)| block.with_context(|| format!("failed to fetch block {height}")))
+        .collect::<Result<Vec<_>>>()?;
+    crate::print_pretty_json(&Value::Array(blocks))
+}
+
+/// Rejects reversed ranges and ranges of more than `max_blocks` heights.
+fn check_range(start: u64, end: u64, max_blocks: u64) -> Result<()> {
+    if end < start {
+        return Err(anyhow!("end height {end} is below start height {start}"));
+    }
+    // Compared before adding 1 so `0..=u64::MAX` cannot overflow.
+    if end - start >= max_blocks {
+        return Err(anyhow!(
+            "range spans {} blocks, more than --max-blocks {max_blocks}",
+            u128::from(end - start) + 1
+        ));
+    }
+    Ok(())
+}
+
+fn by_height_path(height: &str, with_transactions: bool) -> String {
+    format!("/blocks/by_height/{height}?with_transactions={with_transactions}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_range_bounds_without_overflow() {
+        assert!(check_range(5, 5, 1).is_ok());
+        assert!(check_range(0, 99, 100).is_ok());
+        assert_eq!(
+            check_range(0, 100, 100).unwrap_err().to_string(),
+            "range spans 101 blocks, more than --max-blocks 100"
+        );
+        assert_eq!(
+            check_range(7, 3, 100).unwrap_err().to_string(),
+            "end height 3 is below start height 7"
+        );
+        assert_eq!(
+            check_range(0, u64::MAX, 100).unwrap_err().to_string(),
+            "range spans 18446744073709551616 blocks, more than --max-blocks 100"
+        );
+        assert!(check_range(0, u64::MAX, u64::MAX).is_err());
+    }
+}
@@ -1,4 +1,13 @@
-use serde_json::Value;
+use anyhow::{anyhow, Context, Result};
+use aptly_aptos::AptosClient;
+use serde_json::{json, Value};
+
+use crate::JsonArrayWriter;
+
+pub(crate) const OBJECT_CORE_TYPE: &str = "0x1::object::ObjectCore";
+/// Coin types and the FA metadata their balances migrate to. Coins not listed
+/// here are paired through `0x1::coin::paired_metadata`.
+pub(crate) const KNOWN_COIN_FA_PAIRS: &[(&str, &str)] = &[("0x1::aptos_coin::AptosCoin", "0xa")];
 
 pub(crate) fn parse_u64(value: &Value) -> Option<u64> {
     match value {
@@ -27,6 +36,48 @@ pub(crate) fn get_nested_string(value: &Value, keys: &[&str]) -> String {
     value_to_string(current)
 }
 
+/// Whether `address` is the APT fungible asset metadata object (`0xa`),
+/// in either short or zero-padded long form.
+pub(crate) fn is_apt_fa_address(address: &str) -> bool {
+    address
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.trim_start_matches('0').eq_ignore_ascii_case("a"))
+}
+
+/// Lowercased, `0x`-stripped, zero-padded 32-byte hex for comparisons.
+pub(crate) fn normalize_hex_address(value: &str) -> String {
+    let hex = value.trim().trim_start_matches("0x").to_ascii_lowercase();
+    format!("{hex:0>64}")
+}
+
+/// The single gas unit price from an `/estimate_gas_price` response.
+pub(crate) fn gas_unit_price_estimate(value: &Value) -> Option<String> {
+    ["gas_estimate", "gas_unit_price"]
+        .into_iter()
+        .map(|field| get_nested_string(value, &[field]))
+        .find(|price| !price.is_empty())
+}
+
+/// Rewrites every `0x1::option::Option` rendering — an object whose only
+/// field is `vec` holding zero or one element — into `null` or the element.
+pub(crate) fn unwrap_options(value: &mut Value) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(unwrap_options),
+        Value::Object(map) => {
+            map.values_mut().for_each(unwrap_options);
+            if map.len() != 1 {
+                return;
+            }
+            let option = match map.get_mut("vec") {
+                Some(Value::Array(items)) if items.len() <= 1 => items.pop(),
+                _ => return,
+            };
+            *value = option.unwrap_or(Value::Null);
+        }
+        _ => {}
+    }
+}
+
 pub(crate) fn shorten_addr(value: &str) -> String {
     if value.len() > 12 {
         format!("{}...{}", &value[..6], &value[value.len() - 4..])
@@ -35,12 +86,102 @@ pub(crate) fn shorten_addr(value: &str) -> String {
     }
 }
 
-pub(crate) fn with_optional_ledger_version(path: &str, ledger_version: Option<u64>) -> String {
+/// Requests `page_size` items at a time from `start`, advancing by the number
+/// returned, until a short page or `max_pages` pages. Items are streamed to
+/// `writer` as one flat array.
+pub(crate) fn stream_all_pages(
+    start: u64,
+    page_size: u64,
+    max_pages: u64,
+    writer: &mut JsonArrayWriter,
+    mut fetch_page: impl FnMut(u64, u64) -> Result<Vec<Value>>,
+) -> Result<()> {
+    let mut next = start;
+    for _ in 0..max_pages {
+        let page = fetch_page(next, page_size)?;
+        for item in &page {
+            writer.push(item)?;
+        }
+        if (page.len() as u64) < page_size {
+            return Ok(());
+        }
+        next += page.len() as u64;
+    }
+    eprintln!("warning: stopped after {max_pages} pages; raise --max-pages to fetch more");
+    Ok(())
+}
+
+/// Appends `ledger_version` to `path`, first checking the node still serves it.
+pub(crate) fn with_optional_ledger_version(
+    client: &AptosClient,
+    path: &str,
+    ledger_version: Option<u64>,
+) -> Result<String> {
     match ledger_version {
         Some(version) => {
+            client.ensure_version_available(version)?;
             let separator = if path.contains('?') { '&' } else { '?' };
-            format!("{path}{separator}ledger_version={version}")
+            Ok(format!("{path}{separator}ledger_version={version}"))
         }
-        None => path.to_owned(),
+        None => Ok(path.to_owned()),
+    }
+}
+
+/// The `exposed_functions` entry for `function` (`address::module::name`)
+/// from its module's ABI.
+pub(crate) fn fetch_function_abi(
+    client: &AptosClient,
+    function: &str,
+    ledger_version: Option<u64>,
+) -> Result<Value> {
+    let mut parts = function.splitn(3, "::");
+    let (Some(address), Some(module), Some(name)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(anyhow!(
+            "expected `address::module::function`, got `{function}`"
+        ));
+    };
+
+    let path = with_optional_ledger_version(
+        client,
+        &format!("/accounts/{address}/module/{}", urlencoding::encode(module)),
+        ledger_version,
+    )?;
+    let mut module_value = client.get_json_cached(&path)?;
+    let functions = match module_value.pointer_mut("/abi/exposed_functions") {
+        Some(Value::Array(functions)) => std::mem::take(functions),
+        _ => Vec::new(),
+    };
+    functions
+        .into_iter()
+        .find(|item| item.get("name").and_then(Value::as_str) == Some(name))
+        .ok_or_else(|| anyhow!("function `{name}` not found in the ABI of {address}::{module}"))
+}
+
+/// The FA metadata address `coin_type` is paired with, if any.
+pub(crate) fn paired_fa_metadata(
+    client: &AptosClient,
+    coin_type: &str,
+    ledger_version: Option<u64>,
+) -> Result<Option<String>> {
+    if let Some((_, metadata)) = KNOWN_COIN_FA_PAIRS
+        .iter()
+        .find(|(known, _)| *known == coin_type)
+    {
+        return Ok(Some((*metadata).to_owned()));
     }
+
+    let body = json!({
+        "function": "0x1::coin::paired_metadata",
+        "type_arguments": [coin_type],
+        "arguments": [],
+    });
+    let path = with_optional_ledger_version(client, "/view", ledger_version)?;
+    let value = client
+        .post_json_cached(&path, &body)
+        .with_context(|| format!("failed to look up the FA paired with {coin_type}"))?;
+    Ok(value
+        .pointer("/0/vec/0/inner")
+        .and_then(Value::as_str)
+        .map(str::to_owned))
 }
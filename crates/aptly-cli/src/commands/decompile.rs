@@ -1,3 +1,4 @@
+use crate::commands::common::with_optional_ledger_version;
 use crate::plugin_tools::run_move_decompiler;
 use anyhow::{anyhow, Context, Result};
 use aptly_aptos::AptosClient;
@@ -9,7 +10,7 @@ use tempfile::tempdir;
 
 #[derive(Args)]
 #[command(
-    after_help = "Examples:\n  aptly decompile module 0x1 coin\n  aptly decompile address 0x1 --module coin --module aptos_coin\n  aptly decompile raw -- --help\n\nCommon fallback when source metadata is unavailable:\n  aptly decompile address <address>\n  aptly decompile module <address> <module_name>"
+    after_help = "Examples:\n  aptly decompile module 0x1 coin\n  aptly decompile address 0x1 --module coin --module aptos_coin\n  aptly decompile module 0x1 coin --ledger-version 1000000\n  aptly decompile raw -- --help\n\nCommon fallback when source metadata is unavailable:\n  aptly decompile address <address>\n  aptly decompile module <address> <module_name>"
 )]
 pub(crate) struct DecompileCommand {
     #[command(subcommand)]
@@ -44,21 +45,8 @@ pub(crate) struct DecompileModuleArgs {
     /// Module name.
     #[arg(value_name = "MODULE")]
     pub(crate) module: String,
-    /// Explicit move-decompiler binary path.
-    #[arg(long = "decompiler-bin")]
-    pub(crate) decompiler_bin: Option<String>,
-    /// Output directory for decompiled sources.
-    #[arg(long)]
-    pub(crate) out_dir: Option<PathBuf>,
-    /// Keep intermediate `.mv` bytecode files.
-    #[arg(long, default_value_t = false)]
-    pub(crate) keep_bytecode: bool,
-    /// Output file extension for decompiled files.
-    #[arg(long = "ending", default_value = "move")]
-    pub(crate) ending: String,
-    /// Additional move-decompiler argument (repeatable).
-    #[arg(long = "decompiler-arg")]
-    pub(crate) decompiler_args: Vec<String>,
+    #[command(flatten)]
+    pub(crate) options: DecompileOptions,
 }
 
 #[derive(Args)]
@@ -69,6 +57,15 @@ pub(crate) struct DecompileAddressArgs {
     /// Module name filter (repeatable). If omitted, decompile all modules.
     #[arg(long = "module")]
     pub(crate) modules: Vec<String>,
+    #[command(flatten)]
+    pub(crate) options: DecompileOptions,
+}
+
+#[derive(Args)]
+pub(crate) struct DecompileOptions {
+    /// Read module bytecode as of a historical ledger version.
+    #[arg(long)]
+    pub(crate) ledger_version: Option<u64>,
     /// Explicit move-decompiler binary path.
     #[arg(long = "decompiler-bin")]
     pub(crate) decompiler_bin: Option<String>,
@@ -86,59 +83,39 @@ pub(crate) struct DecompileAddressArgs {
     pub(crate) decompiler_args: Vec<String>,
 }
 
-pub(crate) fn run_decompile(rpc_url: &str, command: DecompileCommand) -> Result<()> {
+pub(crate) fn run_decompile(client: &AptosClient, command: DecompileCommand) -> Result<()> {
     match command.command {
         DecompileSubcommand::Raw(args) => {
             run_move_decompiler(args.decompiler_bin.as_deref(), &args.args)
         }
-        DecompileSubcommand::Module(args) => run_decompile_for_modules(
-            rpc_url,
-            &args.address,
-            vec![args.module],
-            args.decompiler_bin.as_deref(),
-            args.out_dir,
-            args.keep_bytecode,
-            &args.ending,
-            &args.decompiler_args,
-        ),
+        DecompileSubcommand::Module(args) => {
+            run_decompile_for_modules(client, &args.address, vec![args.module], args.options)
+        }
         DecompileSubcommand::Address(args) => {
-            let client = AptosClient::new(rpc_url)?;
             let modules = if args.modules.is_empty() {
-                fetch_account_module_names(&client, &args.address)?
+                fetch_account_module_names(client, &args.address, args.options.ledger_version)?
             } else {
                 args.modules
             };
 
-            run_decompile_for_modules(
-                rpc_url,
-                &args.address,
-                modules,
-                args.decompiler_bin.as_deref(),
-                args.out_dir,
-                args.keep_bytecode,
-                &args.ending,
-                &args.decompiler_args,
-            )
+            run_decompile_for_modules(client, &args.address, modules, args.options)
         }
     }
 }
 
 fn run_decompile_for_modules(
-    rpc_url: &str,
+    client: &AptosClient,
     address: &str,
     modules: Vec<String>,
-    decompiler_bin: Option<&str>,
-    out_dir: Option<PathBuf>,
-    keep_bytecode: bool,
-    ending: &str,
-    decompiler_args: &[String],
+    options: DecompileOptions,
 ) -> Result<()> {
     if modules.is_empty() {
         return Err(anyhow!("no modules provided for decompilation"));
     }
 
-    let client = AptosClient::new(rpc_url)?;
-    let output_dir = out_dir.unwrap_or_else(|| default_decompile_output_dir(address));
+    let output_dir = options
+        .out_dir
+        .unwrap_or_else(|| default_decompile_output_dir(address));
     fs::create_dir_all(&output_dir).with_context(|| {
         format!(
             "failed to create decompile output directory {}",
@@ -157,11 +134,12 @@ fn run_decompile_for_modules(
             continue;
         }
 
-        let bytecode_hex = fetch_module_bytecode(&client, address, &module_name)?;
+        let bytecode_hex =
+            fetch_module_bytecode(client, address, &module_name, options.ledger_version)?;
         let file_stem = sanitize_file_component(&module_name);
         let mv_path = bytecode_dir.join(format!("{file_stem}.mv"));
         write_mv_file(&mv_path, &bytecode_hex)?;
-        if keep_bytecode {
+        if options.keep_bytecode {
             let bytecode_out_dir = output_dir.join("bytecode");
             fs::create_dir_all(&bytecode_out_dir)?;
             let destination = bytecode_out_dir.join(format!("{file_stem}.mv"));
@@ -177,14 +155,14 @@ fn run_decompile_for_modules(
     let mut run_args = Vec::new();
     run_args.push("--output-dir".to_owned());
     run_args.push(output_dir.display().to_string());
-    if !ending.trim().is_empty() {
+    if !options.ending.trim().is_empty() {
         run_args.push("--ending".to_owned());
-        run_args.push(ending.to_owned());
+        run_args.push(options.ending.clone());
     }
-    run_args.extend(decompiler_args.iter().cloned());
+    run_args.extend(options.decompiler_args.iter().cloned());
     run_args.extend(mv_files.iter().map(|path| path.display().to_string()));
 
-    run_move_decompiler(decompiler_bin, &run_args)?;
+    run_move_decompiler(options.decompiler_bin.as_deref(), &run_args)?;
     eprintln!(
         "Decompiled {} module(s) for {} into {}",
         mv_files.len(),
@@ -194,11 +172,29 @@ fn run_decompile_for_modules(
     Ok(())
 }
 
-fn fetch_account_module_names(client: &AptosClient, address: &str) -> Result<Vec<String>> {
-    let value = client.get_json(&format!("/accounts/{address}/modules"))?;
-    let modules = value
-        .as_array()
-        .ok_or_else(|| anyhow!("unexpected module list response format"))?;
+/// Every module published under `address`, as returned by `/accounts/{address}/modules`.
+pub(crate) fn fetch_account_modules(
+    client: &AptosClient,
+    address: &str,
+    ledger_version: Option<u64>,
+) -> Result<Vec<Value>> {
+    let path = with_optional_ledger_version(
+        client,
+        &format!("/accounts/{address}/modules"),
+        ledger_version,
+    )?;
+    match client.get_json(&path)? {
+        Value::Array(modules) => Ok(modules),
+        _ => Err(anyhow!("unexpected module list response format")),
+    }
+}
+
+fn fetch_account_module_names(
+    client: &AptosClient,
+    address: &str,
+    ledger_version: Option<u64>,
+) -> Result<Vec<String>> {
+    let modules = fetch_account_modules(client, address, ledger_version)?;
 
     let names: Vec<String> = modules
         .iter()
@@ -217,9 +213,19 @@ fn fetch_account_module_names(client: &AptosClient, address: &str) -> Result<Vec
     Ok(names)
 }
 
-fn fetch_module_bytecode(client: &AptosClient, address: &str, module: &str) -> Result<String> {
+fn fetch_module_bytecode(
+    client: &AptosClient,
+    address: &str,
+    module: &str,
+    ledger_version: Option<u64>,
+) -> Result<String> {
     let encoded = urlencoding::encode(module);
-    let value = client.get_json(&format!("/accounts/{address}/module/{encoded}"))?;
+    let path = with_optional_ledger_version(
+        client,
+        &format!("/accounts/{address}/module/{encoded}"),
+        ledger_version,
+    )?;
+    let value = client.get_json(&path)?;
     let bytecode = value
         .get("bytecode")
         .and_then(Value::as_str)
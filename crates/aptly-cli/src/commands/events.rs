@@ -1,18 +1,49 @@
 use anyhow::Result;
 use aptly_aptos::AptosClient;
-use clap::Args;
+use clap::{Args, Subcommand};
 
 #[derive(Args)]
 #[command(
-    after_help = "Examples:\n  aptly events 0x1 0 --limit 10\n  aptly events 0x1 0 --start 100 --limit 25"
+    after_help = "Examples:\n  aptly events 0x1 0 --limit 10\n  aptly events 0x1 0 --start 100 --limit 25\n  aptly events by-handle 0x1 0x1::block::BlockResource new_block_events --limit 10\n\nUse `by-handle` when the creation number is unknown: name the resource that\nholds the `EventHandle` and the handle's field instead."
 )]
+#[command(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 pub(crate) struct EventsCommand {
+    #[command(subcommand)]
+    pub(crate) command: Option<EventsSubcommand>,
+    /// Account address that owns the event handle.
+    #[arg(value_name = "ADDRESS", required = true)]
+    pub(crate) address: Option<String>,
+    /// Event handle creation number.
+    #[arg(value_name = "CREATION_NUMBER", required = true)]
+    pub(crate) creation_number: Option<String>,
+    /// Maximum number of events to return.
+    #[arg(long, default_value_t = 25)]
+    pub(crate) limit: u64,
+    /// Start cursor (ledger version offset).
+    #[arg(long, default_value_t = 0)]
+    pub(crate) start: u64,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum EventsSubcommand {
+    #[command(
+        name = "by-handle",
+        about = "Fetch events by the resource and field holding the event handle"
+    )]
+    ByHandle(ByHandleArgs),
+}
+
+#[derive(Args)]
+pub(crate) struct ByHandleArgs {
     /// Account address that owns the event handle.
     #[arg(value_name = "ADDRESS")]
     pub(crate) address: String,
-    /// Event handle creation number.
-    #[arg(value_name = "CREATION_NUMBER")]
-    pub(crate) creation_number: String,
+    /// Resource type holding the handle, e.g. `0x1::block::BlockResource`.
+    #[arg(value_name = "EVENT_HANDLE_STRUCT")]
+    pub(crate) event_handle_struct: String,
+    /// Field of that resource holding the handle, e.g. `new_block_events`.
+    #[arg(value_name = "FIELD_NAME")]
+    pub(crate) field_name: String,
     /// Maximum number of events to return.
     #[arg(long, default_value_t = 25)]
     pub(crate) limit: u64,
@@ -22,12 +53,34 @@ pub(crate) struct EventsCommand {
 }
 
 pub(crate) fn run_events(client: &AptosClient, command: EventsCommand) -> Result<()> {
-    let mut path = format!(
-        "/accounts/{}/events/{}?limit={}",
-        command.address, command.creation_number, command.limit
-    );
-    if command.start > 0 {
-        path.push_str(&format!("&start={}", command.start));
+    let (path, limit, start) = match command.command {
+        Some(EventsSubcommand::ByHandle(args)) => (
+            format!(
+                "/accounts/{}/events/{}/{}",
+                args.address,
+                urlencoding::encode(&args.event_handle_struct),
+                urlencoding::encode(&args.field_name)
+            ),
+            args.limit,
+            args.start,
+        ),
+        None => {
+            let (Some(address), Some(creation_number)) = (command.address, command.creation_number)
+            else {
+                // Both are required unless a subcommand is given.
+                unreachable!()
+            };
+            (
+                format!("/accounts/{address}/events/{creation_number}"),
+                command.limit,
+                command.start,
+            )
+        }
+    };
+
+    let mut path = format!("{path}?limit={limit}");
+    if start > 0 {
+        path.push_str(&format!("&start={start}"));
     }
 
     let value = client.get_json(&path)?;
@@ -1,5 +1,6 @@
 pub(crate) mod account;
 pub(crate) mod address;
+pub(crate) mod asset;
 pub(crate) mod block;
 pub(crate) mod common;
 pub(crate) mod decompile;
@@ -7,5 +8,6 @@ pub(crate) mod events;
 pub(crate) mod node;
 pub(crate) mod plugin;
 pub(crate) mod table;
+pub(crate) mod trace;
 pub(crate) mod tx;
 pub(crate) mod view;
@@ -1,10 +1,34 @@
-use anyhow::Result;
-use aptly_aptos::AptosClient;
+use anyhow::{anyhow, Context, Result};
+use aptly_aptos::{AptosClient, AptosError};
 use clap::{Args, Subcommand};
+use reqwest::Url;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::Write;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::commands::common::{
+    gas_unit_price_estimate, get_nested_string, parse_u64, value_to_string,
+};
+
+/// Default port of a node's inspection service (`/peer_information`,
+/// `/consensus_health_check`, `/metrics`).
+const INSPECTION_SERVICE_PORT: u16 = 9101;
+const VALIDATOR_SET_TYPE: &str = "0x1::stake::ValidatorSet";
+const FRAMEWORK_VERSION_TYPE: &str = "0x1::version::Version";
+/// Chain ids of the named Aptos networks (`aptos_types::chain_id::NamedChain`).
+const NAMED_CHAINS: &[(u64, &str)] = &[
+    (1, "mainnet"),
+    (2, "testnet"),
+    (3, "devnet"),
+    (4, "testing"),
+    (5, "premainnet"),
+];
 
 #[derive(Args)]
 #[command(
-    after_help = "Examples:\n  aptly node ledger\n  aptly node health\n  aptly --rpc-url https://rpc.sentio.xyz/aptos/v1 node estimate-gas-price"
+    after_help = "Examples:\n  aptly node ledger\n  aptly node chain-info\n  aptly node health\n  aptly node health --duration-secs 10 --poll --poll-timeout-secs 120\n  aptly node gas-price --percentiles\n  aptly node staking --top 5\n  aptly node raw /blocks/by_height/1\n  aptly node get /accounts/0x1/resource/0x1::chain_id::ChainId\n  aptly --rpc-url http://localhost:8080/v1 node peers\n  aptly node consensus-info --inspection-url http://localhost:9101\n  aptly node raw /view --post '{\"function\":\"0x1::chain_id::get\",\"type_arguments\":[],\"arguments\":[]}'\n  aptly --rpc-url https://rpc.sentio.xyz/aptos/v1 node estimate-gas-price"
 )]
 pub(crate) struct NodeCommand {
     #[command(subcommand)]
@@ -18,21 +42,352 @@ pub(crate) enum NodeSubcommand {
     #[command(about = "Get OpenAPI spec JSON")]
     Spec,
     #[command(about = "Check node health")]
-    Health,
+    Health(NodeHealthArgs),
     #[command(about = "Get node build/runtime info")]
     Info,
-    #[command(name = "estimate-gas-price", about = "Estimate current gas price")]
-    EstimateGasPrice,
+    #[command(
+        name = "chain-info",
+        about = "Summarize chain id, network name, ledger range, and framework version"
+    )]
+    ChainInfo,
+    #[command(
+        name = "estimate-gas-price",
+        visible_alias = "gas-price",
+        about = "Estimate current gas price"
+    )]
+    EstimateGasPrice(NodeGasPriceArgs),
+    #[command(about = "Summarize the validator set and voting power")]
+    Staking(NodeStakingArgs),
+    #[command(
+        visible_alias = "get",
+        about = "GET (or POST with --post) an arbitrary API path"
+    )]
+    Raw(NodeRawArgs),
+    #[command(about = "Print connected peers from the node's inspection service")]
+    Peers(NodeInspectionArgs),
+    #[command(
+        name = "consensus-info",
+        about = "Print the consensus health check from the node's inspection service"
+    )]
+    ConsensusInfo(NodeInspectionArgs),
+}
+
+#[derive(Args)]
+pub(crate) struct NodeInspectionArgs {
+    /// Inspection service base URL (default: the RPC host on port 9101).
+    #[arg(long, value_name = "URL")]
+    pub(crate) inspection_url: Option<String>,
+}
+
+#[derive(Args)]
+pub(crate) struct NodeStakingArgs {
+    /// Number of active validators to list, by voting power.
+    #[arg(long, default_value_t = 10)]
+    pub(crate) top: usize,
+    /// Print the raw `0x1::stake::ValidatorSet` resource instead of a summary.
+    #[arg(long, default_value_t = false)]
+    pub(crate) json: bool,
+}
+
+#[derive(Args)]
+pub(crate) struct NodeGasPriceArgs {
+    /// Print a percentile → gas unit price table when the node reports one,
+    /// otherwise the single estimate.
+    #[arg(long, default_value_t = false)]
+    pub(crate) percentiles: bool,
+}
+
+#[derive(Args)]
+pub(crate) struct NodeRawArgs {
+    /// API path relative to the RPC URL, starting with `/`.
+    #[arg(value_name = "PATH")]
+    pub(crate) path: String,
+    /// POST this JSON body instead of issuing a GET.
+    #[arg(long, value_name = "JSON")]
+    pub(crate) post: Option<String>,
 }
 
-pub(crate) fn run_node(client: &AptosClient, command: NodeCommand) -> Result<()> {
+#[derive(Args)]
+pub(crate) struct NodeHealthArgs {
+    /// Require the latest ledger timestamp to be within N seconds of now.
+    #[arg(long, value_name = "N")]
+    pub(crate) duration_secs: Option<u64>,
+    /// Retry until the node reports healthy; exits non-zero on timeout.
+    #[arg(long, default_value_t = false)]
+    pub(crate) poll: bool,
+    /// Give up polling after this many seconds.
+    #[arg(long, default_value_t = 60)]
+    pub(crate) poll_timeout_secs: u64,
+    /// Delay between polling attempts in milliseconds.
+    #[arg(long, default_value_t = 1000)]
+    pub(crate) poll_interval_ms: u64,
+}
+
+pub(crate) fn run_node(client: &AptosClient, rpc_url: &str, command: NodeCommand) -> Result<()> {
     let value = match command.command {
         NodeSubcommand::Ledger => client.get_json("/")?,
         NodeSubcommand::Spec => client.get_json("/spec.json")?,
-        NodeSubcommand::Health => client.get_json("/-/healthy")?,
+        NodeSubcommand::Health(args) => check_health(client, &args)?,
         NodeSubcommand::Info => client.get_json("/info")?,
-        NodeSubcommand::EstimateGasPrice => client.get_json("/estimate_gas_price")?,
+        NodeSubcommand::ChainInfo => return run_chain_info(client),
+        NodeSubcommand::EstimateGasPrice(args) => {
+            let value = client.get_json("/estimate_gas_price")?;
+            if args.percentiles {
+                return print_gas_percentiles(&value);
+            }
+            value
+        }
+        NodeSubcommand::Raw(args) => fetch_raw(client, &args)?,
+        NodeSubcommand::Staking(args) => return run_staking(client, &args),
+        NodeSubcommand::Peers(args) => {
+            return print_inspection(client, rpc_url, &args, "/peer_information")
+        }
+        NodeSubcommand::ConsensusInfo(args) => {
+            return print_inspection(client, rpc_url, &args, "/consensus_health_check")
+        }
     };
 
     crate::print_pretty_json(&value)
 }
+
+#[derive(Debug, Serialize)]
+struct ChainInfo {
+    chain_id: u64,
+    network: String,
+    ledger_version: u64,
+    oldest_ledger_version: u64,
+    epoch: u64,
+    framework_version: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct StakingSummary {
+    active_validators: usize,
+    pending_active: usize,
+    pending_inactive: usize,
+    total_voting_power: String,
+    total_joining_power: String,
+    top_validators: Vec<ValidatorPower>,
+}
+
+#[derive(Debug, Serialize)]
+struct ValidatorPower {
+    address: String,
+    voting_power: String,
+}
+
+fn run_chain_info(client: &AptosClient) -> Result<()> {
+    let ledger = client.get_json("/")?;
+    let field = |name: &str| {
+        parse_u64(ledger.get(name).unwrap_or(&Value::Null))
+            .ok_or_else(|| anyhow!("ledger info missing `{name}` field"))
+    };
+    let chain_id = field("chain_id")?;
+    let network = NAMED_CHAINS
+        .iter()
+        .find(|(id, _)| *id == chain_id)
+        .map_or("unknown", |(_, name)| name)
+        .to_owned();
+
+    let encoded = urlencoding::encode(FRAMEWORK_VERSION_TYPE);
+    let framework_version = match client.get_json(&format!("/accounts/0x1/resource/{encoded}")) {
+        Ok(resource) => resource
+            .get("data")
+            .and_then(|data| data.get("major"))
+            .and_then(parse_u64),
+        Err(AptosError::NotFound(_)) => None,
+        Err(err) => return Err(err.into()),
+    };
+
+    crate::print_serialized(&ChainInfo {
+        chain_id,
+        network,
+        ledger_version: field("ledger_version")?,
+        oldest_ledger_version: field("oldest_ledger_version")?,
+        epoch: field("epoch")?,
+        framework_version,
+    })
+}
+
+fn run_staking(client: &AptosClient, args: &NodeStakingArgs) -> Result<()> {
+    let encoded = urlencoding::encode(VALIDATOR_SET_TYPE);
+    let resource = client.get_json(&format!("/accounts/0x1/resource/{encoded}"))?;
+    if args.json {
+        return crate::print_pretty_json(&resource);
+    }
+
+    let data = resource
+        .get("data")
+        .ok_or_else(|| anyhow!("validator set resource missing `data` field"))?;
+    let validators = |field: &str| {
+        data.get(field)
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    };
+
+    let mut top_validators: Vec<ValidatorPower> = validators("active_validators")
+        .iter()
+        .map(|validator| ValidatorPower {
+            address: get_nested_string(validator, &["addr"]),
+            voting_power: value_to_string(validator.get("voting_power").unwrap_or(&Value::Null)),
+        })
+        .collect();
+    top_validators.sort_by_key(|validator| {
+        std::cmp::Reverse(validator.voting_power.parse::<u128>().unwrap_or(0))
+    });
+    top_validators.truncate(args.top);
+
+    crate::print_serialized(&StakingSummary {
+        active_validators: validators("active_validators").len(),
+        pending_active: validators("pending_active").len(),
+        pending_inactive: validators("pending_inactive").len(),
+        total_voting_power: get_nested_string(data, &["total_voting_power"]),
+        total_joining_power: get_nested_string(data, &["total_joining_power"]),
+        top_validators,
+    })
+}
+
+fn print_gas_percentiles(value: &Value) -> Result<()> {
+    let percentiles = gas_price_percentiles(value);
+    if percentiles.is_empty() {
+        let estimate = gas_unit_price_estimate(value)
+            .ok_or_else(|| anyhow!("gas price response has no estimate"))?;
+        writeln!(
+            crate::pager::stdout(),
+            "no percentile data from node; estimate: {estimate}"
+        )?;
+        return Ok(());
+    }
+
+    let label_width = percentiles.iter().map(|(p, _)| p.len()).max().unwrap_or(0);
+    let price_width = percentiles.iter().map(|(_, g)| g.len()).max().unwrap_or(0);
+    let mut out = crate::pager::stdout();
+    for (percentile, price) in &percentiles {
+        writeln!(out, "{percentile:<label_width$} {price:>price_width$}")?;
+    }
+    Ok(())
+}
+
+/// Reads a `percentiles` map (`{"p50": "100"}`) or list
+/// (`[{"percentile": 50, "gas_unit_price": "100"}]`).
+fn gas_price_percentiles(value: &Value) -> Vec<(String, String)> {
+    match value.get("percentiles") {
+        Some(Value::Object(map)) => map
+            .iter()
+            .map(|(percentile, price)| (percentile.clone(), value_to_string(price)))
+            .collect(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .filter_map(|item| {
+                let percentile = value_to_string(item.get("percentile")?);
+                let price = item.get("gas_unit_price").or_else(|| item.get("price"))?;
+                Some((format!("p{percentile}"), value_to_string(price)))
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn fetch_raw(client: &AptosClient, args: &NodeRawArgs) -> Result<Value> {
+    if !args.path.starts_with('/') {
+        return Err(anyhow!("path must start with `/`, got `{}`", args.path));
+    }
+    let path = url_safe_path(&args.path);
+    match &args.post {
+        Some(body) => {
+            let body: Value =
+                serde_json::from_str(body).context("failed to parse --post body as JSON")?;
+            Ok(client.post_json(&path, &body)?)
+        }
+        None => Ok(client.get_json(&path)?),
+    }
+}
+
+/// Percent-encodes the characters that would otherwise change how the path
+/// is read (`#` starting a fragment, whitespace, quotes); everything else,
+/// including `?`/`&` query syntax and `::`/`<>` in type tags, is kept.
+fn url_safe_path(path: &str) -> String {
+    let mut safe = String::with_capacity(path.len());
+    for ch in path.chars() {
+        if matches!(ch, '#' | '"' | '`') || ch.is_whitespace() {
+            safe.push_str(&urlencoding::encode(ch.encode_utf8(&mut [0; 4])));
+        } else {
+            safe.push(ch);
+        }
+    }
+    safe
+}
+
+/// The inspection service serves plain text on its own port, so it is read
+/// with a bare HTTP client rather than through the node API client.
+fn print_inspection(
+    client: &AptosClient,
+    rpc_url: &str,
+    args: &NodeInspectionArgs,
+    path: &str,
+) -> Result<()> {
+    let base = match &args.inspection_url {
+        Some(url) => url.trim().trim_end_matches('/').to_owned(),
+        None => inspection_url_for(rpc_url)?,
+    };
+    let url = format!("{base}{path}");
+    let response = client
+        .http_config()
+        .build_client()?
+        .get(&url)
+        .send()
+        .with_context(|| {
+            format!("request failed: GET {url}; pass --inspection-url if the service is elsewhere")
+        })?;
+    let status = response.status();
+    let text = response
+        .text()
+        .context("failed to read inspection service response body")?;
+    if !status.is_success() {
+        return Err(anyhow!(
+            "inspection service error (status {}): {}",
+            status.as_u16(),
+            text.trim()
+        ));
+    }
+
+    writeln!(crate::pager::stdout(), "{}", text.trim_end())?;
+    Ok(())
+}
+
+fn inspection_url_for(rpc_url: &str) -> Result<String> {
+    let mut url =
+        Url::parse(rpc_url.trim()).with_context(|| format!("invalid RPC URL `{rpc_url}`"))?;
+    url.set_port(Some(INSPECTION_SERVICE_PORT))
+        .map_err(|_| anyhow!("cannot derive an inspection URL from `{rpc_url}`"))?;
+    url.set_path("");
+    url.set_query(None);
+    Ok(url.as_str().trim_end_matches('/').to_owned())
+}
+
+fn check_health(client: &AptosClient, args: &NodeHealthArgs) -> Result<Value> {
+    let path = match args.duration_secs {
+        Some(duration_secs) => format!("/-/healthy?duration_secs={duration_secs}"),
+        None => "/-/healthy".to_owned(),
+    };
+    if !args.poll {
+        return Ok(client.get_json(&path)?);
+    }
+
+    let timeout = Duration::from_secs(args.poll_timeout_secs);
+    let interval = Duration::from_millis(args.poll_interval_ms);
+    let started = Instant::now();
+    loop {
+        match client.get_json(&path) {
+            Ok(value) => return Ok(value),
+            Err(err) if started.elapsed() + interval > timeout => {
+                return Err(anyhow!(
+                    "node not healthy after {}s: {err:#}",
+                    args.poll_timeout_secs
+                ));
+            }
+            Err(_) => thread::sleep(interval),
+        }
+    }
+}
@@ -1,13 +1,14 @@
 use crate::plugin_tools::{
     discover_aptos_script_compose, discover_aptos_tracer, discover_move_decompiler,
     doctor_aptos_script_compose, doctor_aptos_tracer, doctor_move_decompiler,
+    run_plugin_passthrough, PLUGIN_NAMES,
 };
 use anyhow::{anyhow, Result};
 use clap::{Args, Subcommand};
 
 #[derive(Args)]
 #[command(
-    after_help = "Examples:\n  aptly plugin list\n  aptly plugin doctor\n  aptly plugin doctor --decompiler-bin ./target/cli/move-decompiler"
+    after_help = "Examples:\n  aptly plugin list\n  aptly plugin doctor\n  aptly plugin doctor --decompiler-bin ./target/cli/move-decompiler\n  aptly plugin run aptos-tracer -- --help\n  aptly plugin run aptos-script-compose --bin ./target/release/aptos-script-compose -- --help"
 )]
 pub(crate) struct PluginCommand {
     #[command(subcommand)]
@@ -20,6 +21,8 @@ pub(crate) enum PluginSubcommand {
     List,
     #[command(about = "Run health checks for plugin binaries")]
     Doctor(PluginDoctorArgs),
+    #[command(about = "Run a discovered plugin binary with raw arguments")]
+    Run(PluginRunArgs),
 }
 
 #[derive(Args)]
@@ -35,6 +38,19 @@ pub(crate) struct PluginDoctorArgs {
     pub(crate) script_compose_bin: Option<String>,
 }
 
+#[derive(Args)]
+pub(crate) struct PluginRunArgs {
+    /// Plugin name (`move-decompiler`, `aptos-tracer`, or `aptos-script-compose`).
+    #[arg(value_name = "NAME", value_parser = clap::builder::PossibleValuesParser::new(PLUGIN_NAMES))]
+    pub(crate) name: String,
+    /// Explicit plugin binary path, bypassing PATH discovery.
+    #[arg(long = "bin")]
+    pub(crate) bin: Option<String>,
+    /// Arguments passed through to the plugin.
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true, num_args = 0..)]
+    pub(crate) args: Vec<String>,
+}
+
 pub(crate) fn run_plugin(command: PluginCommand) -> Result<()> {
     match command.command {
         PluginSubcommand::List => {
@@ -45,6 +61,9 @@ pub(crate) fn run_plugin(command: PluginCommand) -> Result<()> {
             ];
             crate::print_serialized(&plugins)
         }
+        PluginSubcommand::Run(args) => {
+            run_plugin_passthrough(&args.name, args.bin.as_deref(), &args.args)
+        }
         PluginSubcommand::Doctor(args) => {
             let reports = vec![
                 doctor_move_decompiler(args.decompiler_bin.as_deref()),
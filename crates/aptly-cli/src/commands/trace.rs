@@ -0,0 +1,144 @@
+use clap::ValueEnum;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::commands::common::parse_u64;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum TraceRender {
+    /// The tracer's JSON, as returned.
+    #[default]
+    Json,
+    /// An indented call tree with gas per call.
+    Tree,
+    /// Folded stacks for `flamegraph.pl`, weighted by self gas.
+    Flamegraph,
+}
+
+/// One call in a Sentio / aptos-tracer call trace. Only the fields the
+/// renderers need are kept; `--depth` markers (`{truncated, omitted}`)
+/// deserialize as frames with `truncated` set.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub(crate) struct CallFrame {
+    contract_name: String,
+    function_name: String,
+    #[serde(alias = "gas_used")]
+    gas_used: Value,
+    calls: Vec<CallFrame>,
+    truncated: bool,
+    omitted: usize,
+}
+
+impl CallFrame {
+    fn name(&self) -> String {
+        match (self.contract_name.is_empty(), self.function_name.is_empty()) {
+            (false, false) => format!("{}::{}", self.contract_name, self.function_name),
+            (false, true) => self.contract_name.clone(),
+            (true, false) => self.function_name.clone(),
+            (true, true) => "<unknown>".to_owned(),
+        }
+    }
+
+    fn gas(&self) -> Option<u64> {
+        parse_u64(&self.gas_used)
+    }
+}
+
+/// One line per call, indented two spaces per depth level.
+pub(crate) fn render_tree(root: &CallFrame) -> String {
+    let mut out = String::new();
+    write_tree(&mut out, root, 0);
+    out
+}
+
+fn write_tree(out: &mut String, frame: &CallFrame, depth: usize) {
+    out.push_str(&"  ".repeat(depth));
+    if frame.truncated {
+        out.push_str(&format!("… {} call(s) omitted\n", frame.omitted));
+        return;
+    }
+    out.push_str(&frame.name());
+    if let Some(gas) = frame.gas() {
+        out.push_str(&format!("  gas={gas}"));
+    }
+    out.push('\n');
+    for call in &frame.calls {
+        write_tree(out, call, depth + 1);
+    }
+}
+
+/// `root;child;.. weight` lines. The weight is the call's gas minus its
+/// children's, or 1 per call when the trace carries no gas.
+pub(crate) fn render_folded(root: &CallFrame) -> String {
+    let with_gas = root.gas().is_some();
+    let mut out = String::new();
+    let mut stack = Vec::new();
+    write_folded(&mut out, &mut stack, root, with_gas);
+    out
+}
+
+fn write_folded(out: &mut String, stack: &mut Vec<String>, frame: &CallFrame, with_gas: bool) {
+    if frame.truncated {
+        return;
+    }
+    stack.push(frame.name());
+    let weight = if with_gas {
+        let children: u64 = frame.calls.iter().filter_map(CallFrame::gas).sum();
+        frame.gas().unwrap_or(0).saturating_sub(children)
+    } else {
+        1
+    };
+    if weight > 0 {
+        out.push_str(&format!("{} {weight}\n", stack.join(";")));
+    }
+    for call in &frame.calls {
+        write_folded(out, stack, call, with_gas);
+    }
+    stack.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample() -> CallFrame {
+        serde_json::from_value(json!({
+            "contractName": "0x1::aptos_account",
+            "functionName": "transfer",
+            "gasUsed": 10,
+            "calls": [
+                {"contractName": "0x1::coin", "functionName": "withdraw", "gasUsed": "4", "calls": [
+                    {"truncated": true, "omitted": 3}
+                ]},
+                {"contractName": "0x1::coin", "functionName": "deposit", "gasUsed": 5}
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn renders_indented_tree_with_gas_and_omitted_marker() {
+        assert_eq!(
+            render_tree(&sample()),
+            "0x1::aptos_account::transfer  gas=10\n  0x1::coin::withdraw  gas=4\n    … 3 call(s) omitted\n  0x1::coin::deposit  gas=5\n"
+        );
+    }
+
+    #[test]
+    fn folds_stacks_by_self_gas_or_call_count() {
+        assert_eq!(
+            render_folded(&sample()),
+            "0x1::aptos_account::transfer 1\n\
+             0x1::aptos_account::transfer;0x1::coin::withdraw 4\n\
+             0x1::aptos_account::transfer;0x1::coin::deposit 5\n"
+        );
+
+        let no_gas: CallFrame = serde_json::from_value(json!({
+            "contractName": "m", "functionName": "f", "calls": [{"contractName": "m", "functionName": "g"}]
+        }))
+        .unwrap();
+        assert_eq!(render_folded(&no_gas), "m::f 1\nm::f;m::g 1\n");
+    }
+}
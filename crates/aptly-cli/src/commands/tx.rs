@@ -1,31 +1,47 @@
 use crate::plugin_tools::{resolve_aptos_script_compose_bin, resolve_aptos_tracer_bin};
 use anyhow::{anyhow, Context, Result};
-use aptly_aptos::AptosClient;
+use aptly_aptos::{substitute_type_parameters, AptosClient, AptosError, HttpConfig};
 use clap::{Args, Subcommand};
 use num_bigint::BigInt;
 use serde::Serialize;
 use serde_json::{json, Value};
-use std::collections::HashMap;
-use std::io::{self, IsTerminal, Read};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::str::FromStr;
-use std::time::Duration;
-
-use crate::commands::common::{get_nested_string, parse_u64, value_to_string};
-
-const OBJECT_CORE_TYPE: &str = "0x1::object::ObjectCore";
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::commands::asset::{format_amount, AssetResolver};
+use crate::commands::common::{
+    fetch_function_abi, gas_unit_price_estimate, get_nested_string, is_apt_fa_address,
+    normalize_hex_address, paired_fa_metadata, parse_u64, stream_all_pages, value_to_string,
+    with_optional_ledger_version, OBJECT_CORE_TYPE,
+};
+use crate::commands::trace::{render_folded, render_tree, CallFrame, TraceRender};
+use crate::JsonArrayWriter;
+
+const APT_DECIMALS: u8 = 8;
+const DEFAULT_SIMULATE_MAX_GAS: u64 = 200_000;
+/// First-pass cap for `--estimate-max-gas`: the network's per-transaction
+/// gas limit, so heavy transactions still run to completion.
+const ESTIMATE_FIRST_PASS_MAX_GAS: u64 = 2_000_000;
 const FUNGIBLE_STORE_TYPE: &str = "0x1::fungible_asset::FungibleStore";
 const DEFAULT_TRACER_REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
 const SENTIO_TRACE_BASE_URL: &str = "https://app.sentio.xyz";
+/// The node's maximum `limit` for `/transactions`.
+const MAX_TXS_PAGE_SIZE: u64 = 100;
 
 #[derive(Args)]
 #[command(
-    after_help = "Examples:\n  aptly tx 4300326632\n  aptly tx 0xf44b2ea4a0cd55a31559fc022a2fba12aa81c46dcfce31a050d9d42d93a7dae5\n  aptly tx list --limit 10 --start 0\n  aptly tx encode < unsigned_txn.json\n  aptly tx simulate 0x1 < payload.json\n  aptly tx submit < signed_txn.json\n  aptly tx compose < compose_payload.json\n  aptly tx trace 4300326632 --local-tracer\n  aptly tx balance-change 4300326632 --aggregate"
+    after_help = "Examples:\n  aptly tx 4300326632\n  aptly tx latest\n  aptly tx latest-10\n  aptly tx 0xf44b2ea4a0cd55a31559fc022a2fba12aa81c46dcfce31a050d9d42d93a7dae5\n  aptly tx list --limit 10 --start 0\n  aptly tx list --start 4300326600 --all --max-pages 2\n  aptly tx batch --input versions.txt --concurrency 8\n  aptly tx encode < unsigned_txn.json\n  aptly tx submit --file signed_txn.json\n  aptly tx simulate 0x1 < payload.json\n  aptly tx simulate 0x1 --gas-profile < payload.json\n  aptly tx simulate 0x1 --estimate-max-gas --gas-profile < payload.json\n  aptly tx submit < signed_txn.json\n  aptly tx submit --fail-fast < signed_txns.json\n  aptly tx wait 0xf44b2ea4a0cd55a31559fc022a2fba12aa81c46dcfce31a050d9d42d93a7dae5 --wait-secs 60\n  aptly tx decode-payload 4300326632\n  aptly tx compose < compose_payload.json\n  aptly tx compose --validate-only < compose_payload.json\n  aptly tx compose --emit script-payload < compose_payload.json | aptly tx simulate 0x1\n  aptly tx trace 4300326632 --local-tracer\n  aptly tx trace 4300326632 --depth 2\n  aptly tx trace 4300326632 --render tree\n  aptly tx trace 4300326632 --render flamegraph | flamegraph.pl > trace.svg\n  aptly tx balance-change 4300326632 --aggregate\n  aptly tx balance-change 4300326632 --aggregate --pretty --precision 4\n  aptly tx balance-change 4300326632 --aggregate --net-only --min-abs 1000\n  aptly tx balance-change 4300326632 --aggregate --no-gas\n  aptly tx balance-change --file txn.json\n  aptly tx export 4300326632 --out ./bundle"
 )]
 pub(crate) struct TxCommand {
     #[command(subcommand)]
     pub(crate) command: Option<TxSubcommand>,
-    /// Transaction version (u64) or hash (0x...).
+    /// Transaction version (u64), hash (0x...), `latest`, or `latest-N`.
     /// Used when no subcommand is provided.
     #[arg(value_name = "VERSION_OR_HASH")]
     pub(crate) version_or_hash: Option<String>,
@@ -35,21 +51,34 @@ pub(crate) struct TxCommand {
 pub(crate) enum TxSubcommand {
     #[command(about = "List transactions from node API")]
     List(TxListArgs),
-    #[command(about = "Encode an unsigned transaction JSON from stdin")]
-    Encode,
-    #[command(about = "Simulate an entry function payload JSON from stdin")]
+    #[command(about = "Fetch many transactions by version or hash concurrently")]
+    Batch(TxBatchArgs),
+    #[command(about = "Encode an unsigned transaction JSON from stdin or --file")]
+    Encode(TxInputArgs),
+    #[command(about = "Simulate an entry function payload JSON from stdin or --file")]
     Simulate(TxSimulateArgs),
-    #[command(about = "Submit a signed transaction JSON from stdin")]
-    Submit,
+    #[command(
+        about = "Submit a signed transaction JSON, or an array of them, from stdin or --file"
+    )]
+    Submit(TxSubmitArgs),
     #[command(about = "Compose script bytecode from batched call payload JSON on stdin")]
     Compose(TxComposeArgs),
     #[command(about = "Fetch and print transaction call trace")]
     Trace(TxTraceArgs),
     #[command(
         name = "balance-change",
-        about = "Summarize fungible asset balance changes for a transaction"
+        about = "Summarize fungible asset and coin balance changes for a transaction"
     )]
     BalanceChange(TxBalanceChangeArgs),
+    #[command(about = "Save a transaction, its trace, and touched resources as a replay bundle")]
+    Export(TxExportArgs),
+    #[command(about = "Poll until a transaction is committed, then print it")]
+    Wait(TxWaitArgs),
+    #[command(
+        name = "decode-payload",
+        about = "Pair entry function arguments with their ABI parameter types"
+    )]
+    DecodePayload(TxDecodePayloadArgs),
 }
 
 #[derive(Args)]
@@ -60,17 +89,125 @@ pub(crate) struct TxListArgs {
     /// Start cursor (ledger version offset).
     #[arg(long, default_value_t = 0)]
     pub(crate) start: u64,
+    /// Follow pagination from `--start` until the last page, as one array.
+    #[arg(long, conflicts_with = "limit")]
+    pub(crate) all: bool,
+    /// With `--all`, stop after this many pages.
+    #[arg(long, default_value_t = 50, requires = "all")]
+    pub(crate) max_pages: u64,
+}
+
+#[derive(Args)]
+pub(crate) struct TxBatchArgs {
+    /// File with one version/hash per line (`#` comments allowed) or a JSON
+    /// array of them.
+    #[arg(long, value_name = "FILE")]
+    pub(crate) input: PathBuf,
+}
+
+#[derive(Args)]
+pub(crate) struct TxInputArgs {
+    /// Read the JSON input from a file instead of stdin.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) file: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub(crate) struct TxSubmitArgs {
+    /// Read the JSON input from a file instead of stdin.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) file: Option<PathBuf>,
+    /// With an array of transactions, stop at the first failed submission.
+    /// Without it, every transaction is tried and the command still fails
+    /// if any of them did.
+    #[arg(long, default_value_t = false)]
+    pub(crate) fail_fast: bool,
 }
 
 #[derive(Args)]
 pub(crate) struct TxBalanceChangeArgs {
-    /// Transaction version (u64) or hash (0x...).
+    /// Transaction version (u64), hash (0x...), `latest`, or `latest-N`.
     /// If omitted, reads full transaction JSON from stdin.
     #[arg(value_name = "VERSION_OR_HASH")]
     pub(crate) version_or_hash: Option<String>,
+    /// Read full transaction JSON from a file. Takes precedence over stdin
+    /// and VERSION_OR_HASH.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) file: Option<PathBuf>,
     /// Aggregate deltas by `(account, asset)` pair.
     #[arg(long, default_value_t = false)]
     pub(crate) aggregate: bool,
+    /// Fail on gas or event amounts that are not integers instead of
+    /// treating them as zero.
+    #[arg(long, default_value_t = false)]
+    pub(crate) strict: bool,
+    /// With `--aggregate`, drop entries whose net amount is zero.
+    #[arg(long, default_value_t = false, requires = "aggregate")]
+    pub(crate) net_only: bool,
+    /// With `--aggregate`, drop entries whose absolute net amount (in raw
+    /// units) is below N.
+    #[arg(long, value_name = "N", requires = "aggregate")]
+    pub(crate) min_abs: Option<BigInt>,
+    /// Show amounts in whole units using each asset's decimals, with its symbol.
+    #[arg(long, default_value_t = false)]
+    pub(crate) pretty: bool,
+    /// With `--pretty`, round amounts half-up to at most N fractional digits
+    /// (default: full precision).
+    #[arg(long, value_name = "N", requires = "pretty")]
+    pub(crate) precision: Option<u8>,
+    /// Leave out the `gas_fee` entry, so only token flows remain (also in
+    /// `--aggregate` totals).
+    #[arg(long, default_value_t = false)]
+    pub(crate) no_gas: bool,
+}
+
+#[derive(Args)]
+pub(crate) struct TxExportArgs {
+    /// Transaction version (u64), hash (0x...), `latest`, or `latest-N`.
+    #[arg(value_name = "VERSION_OR_HASH")]
+    pub(crate) version_or_hash: String,
+    /// Directory to write the bundle into; created if missing.
+    #[arg(long, value_name = "DIR")]
+    pub(crate) out: PathBuf,
+    /// Trace with a local aptos-tracer binary instead of Sentio hosted tracing.
+    #[arg(long = "local-tracer", num_args = 0..=1, value_name = "TRACER_BIN")]
+    pub(crate) local_tracer: Option<Option<String>>,
+    /// Leave the call trace out of the bundle.
+    #[arg(long, conflicts_with = "local_tracer")]
+    pub(crate) no_trace: bool,
+}
+
+#[derive(Args)]
+pub(crate) struct TxWaitArgs {
+    /// Transaction hash (0x...) or version.
+    #[arg(value_name = "VERSION_OR_HASH")]
+    pub(crate) version_or_hash: String,
+    /// Give up after this many seconds. Each poll request is still bounded by
+    /// the global `--timeout-secs`.
+    #[arg(long, value_name = "SECS", default_value_t = 30)]
+    pub(crate) wait_secs: u64,
+    /// Milliseconds between polls.
+    #[arg(long, value_name = "MS", default_value_t = 500, value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) interval_ms: u64,
+}
+
+#[derive(Args)]
+pub(crate) struct TxDecodePayloadArgs {
+    /// Transaction version (u64), hash (0x...), `latest`, or `latest-N`.
+    #[arg(value_name = "VERSION_OR_HASH")]
+    pub(crate) version_or_hash: String,
+}
+
+#[derive(Serialize)]
+struct ExportManifest {
+    aptly_version: &'static str,
+    chain_id: u16,
+    version: u64,
+    hash: String,
+    transaction: String,
+    trace: Option<String>,
+    trace_error: Option<String>,
+    resources: BTreeMap<String, String>,
 }
 
 #[derive(Args)]
@@ -78,11 +215,28 @@ pub(crate) struct TxSimulateArgs {
     /// Sender account address used to resolve sequence number.
     #[arg(value_name = "SENDER")]
     pub(crate) sender: String,
+    /// Read the payload JSON from a file instead of stdin.
+    #[arg(long, value_name = "PATH")]
+    pub(crate) file: Option<PathBuf>,
+    /// `max_gas_amount` for the simulation (the first pass with
+    /// `--estimate-max-gas`). Defaults to 200000, or 2000000 for that first pass.
+    #[arg(long, value_name = "N")]
+    pub(crate) max_gas: Option<u64>,
+    /// Re-simulate with `max_gas_amount` set to the first pass's `gas_used` plus 20%.
+    /// Fails if the first pass does not succeed.
+    #[arg(long, default_value_t = false)]
+    pub(crate) estimate_max_gas: bool,
+    /// Print a gas and fee summary instead of the full simulation result.
+    #[arg(long, default_value_t = false)]
+    pub(crate) gas_profile: bool,
+    /// With `--gas-profile`, include the full simulation result as `simulation`.
+    #[arg(long, default_value_t = false, requires = "gas_profile")]
+    pub(crate) verbose: bool,
 }
 
 #[derive(Args)]
 pub(crate) struct TxTraceArgs {
-    /// Transaction version (u64) or hash (0x...).
+    /// Transaction version (u64), hash (0x...), `latest`, or `latest-N`.
     #[arg(value_name = "VERSION_OR_HASH")]
     pub(crate) version_or_hash: String,
     /// Use a local aptos-tracer binary instead of Sentio hosted tracing.
@@ -90,6 +244,13 @@ pub(crate) struct TxTraceArgs {
     /// RPC is very fast (for example, your own node).
     #[arg(long = "local-tracer", num_args = 0..=1, value_name = "TRACER_BIN")]
     pub(crate) local_tracer: Option<Option<String>>,
+    /// Prune the call tree below depth N (the top-level call is depth 0).
+    #[arg(long, value_name = "N")]
+    pub(crate) depth: Option<usize>,
+    /// How to print the trace: the tracer's JSON, an indented call tree, or
+    /// folded stacks for `flamegraph.pl`.
+    #[arg(long, value_enum, default_value_t = TraceRender::Json)]
+    pub(crate) render: TraceRender,
 }
 
 #[derive(Args)]
@@ -103,6 +264,19 @@ pub(crate) struct TxComposeArgs {
     /// Emit script payload JSON instead of raw 0x-prefixed script bytes.
     #[arg(long, default_value_t = false)]
     pub(crate) emit_script_payload: bool,
+    /// Output encoding: `bytes`, `script-payload`, or `both` (one JSON object
+    /// with `script_bytes_hex` and `script_payload`).
+    #[arg(long, value_name = "FORMAT", value_parser = ["bytes", "script-payload", "both"], conflicts_with = "emit_script_payload")]
+    pub(crate) emit: Option<String>,
+    /// Print a human-readable summary of each batched call to stderr.
+    #[arg(long, default_value_t = false)]
+    pub(crate) explain: bool,
+    /// Resolve modules from local `<addr>_<module>.mv` files before the RPC.
+    #[arg(long, value_name = "DIR")]
+    pub(crate) modules_dir: Option<PathBuf>,
+    /// List every problem in the payload and exit without contacting the RPC.
+    #[arg(long, default_value_t = false)]
+    pub(crate) validate_only: bool,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -113,6 +287,45 @@ struct BalanceChange {
     fungible_store: String,
     asset: String,
     amount: String,
+    /// Set with `--pretty`, when `amount` is in whole units.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symbol: Option<String>,
+    /// Set on `gas_fee` for sponsored transactions, where `account` is the
+    /// fee payer rather than the sender.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fee_payer: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct GasProfile {
+    success: bool,
+    vm_status: String,
+    gas_used: u64,
+    gas_unit_price: u64,
+    max_gas_amount: u64,
+    /// `gas_used * gas_unit_price`, in octas.
+    fee_octas: String,
+    fee_apt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    simulation: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct BatchItem {
+    input: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transaction: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct SubmitItem {
+    index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -120,6 +333,8 @@ struct AggregatedBalanceChange {
     account: String,
     asset: String,
     amount: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    symbol: Option<String>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -131,6 +346,22 @@ struct TransferStoreMetadata {
 pub(crate) fn run_tx(client: &AptosClient, rpc_url: &str, command: TxCommand) -> Result<()> {
     match (command.command, command.version_or_hash) {
         (Some(TxSubcommand::List(args)), _) => {
+            if args.all {
+                let mut writer = JsonArrayWriter::stdout();
+                stream_all_pages(
+                    args.start,
+                    MAX_TXS_PAGE_SIZE,
+                    args.max_pages,
+                    &mut writer,
+                    |start, limit| match client
+                        .get_json(&format!("/transactions?start={start}&limit={limit}"))?
+                    {
+                        Value::Array(txs) => Ok(txs),
+                        _ => Err(anyhow!("unexpected transactions response format")),
+                    },
+                )?;
+                return writer.finish();
+            }
             let mut path = format!("/transactions?limit={}", args.limit);
             if args.start > 0 {
                 path.push_str(&format!("&start={}", args.start));
@@ -138,42 +369,178 @@ pub(crate) fn run_tx(client: &AptosClient, rpc_url: &str, command: TxCommand) ->
             let value = client.get_json(&path)?;
             crate::print_pretty_json(&value)
         }
-        (Some(TxSubcommand::Encode), _) => run_tx_encode(client),
+        (Some(TxSubcommand::Batch(args)), _) => run_tx_batch(client, &args),
+        (Some(TxSubcommand::Encode(args)), _) => run_tx_encode(client, &args),
         (Some(TxSubcommand::Simulate(args)), _) => run_tx_simulate(client, &args),
         (Some(TxSubcommand::Compose(args)), _) => run_tx_compose(rpc_url, &args),
         (Some(TxSubcommand::Trace(args)), _) => run_tx_trace(client, rpc_url, &args),
-        (Some(TxSubcommand::Submit), _) => {
-            let reader = io::stdin();
-            let txn: Value = serde_json::from_reader(reader.lock())
-                .context("failed to parse signed transaction JSON from stdin")?;
-            let value = client.post_json("/transactions", &txn)?;
-            crate::print_pretty_json(&value)
-        }
+        (Some(TxSubcommand::Submit(args)), _) => run_tx_submit(client, &args),
         (Some(TxSubcommand::BalanceChange(args)), _) => run_tx_balance_change(client, &args),
+        (Some(TxSubcommand::Export(args)), _) => run_tx_export(client, rpc_url, &args),
+        (Some(TxSubcommand::Wait(args)), _) => run_tx_wait(client, &args),
+        (Some(TxSubcommand::DecodePayload(args)), _) => run_tx_decode_payload(client, &args),
         (None, Some(version_or_hash)) => {
-            let path = if version_or_hash.parse::<u64>().is_ok() {
-                format!("/transactions/by_version/{version_or_hash}")
-            } else {
-                format!("/transactions/by_hash/{version_or_hash}")
-            };
-            let value = client.get_json(&path)?;
+            let value = client.get_json(&transaction_path(client, &version_or_hash)?)?;
             crate::print_pretty_json(&value)
         }
         (None, None) => Err(anyhow!("missing version/hash or subcommand")),
     }
 }
 
-fn run_tx_encode(client: &AptosClient) -> Result<()> {
-    let reader = io::stdin();
-    let txn: Value = serde_json::from_reader(reader.lock())
-        .context("failed to parse unsigned transaction JSON from stdin")?;
+/// Fetches every reference with the client's batch concurrency
+/// (`--concurrency`); failures are reported per entry.
+fn run_tx_batch(client: &AptosClient, args: &TxBatchArgs) -> Result<()> {
+    let refs = read_batch_refs(&args.input)?;
+    let paths: Vec<Result<String>> = refs
+        .iter()
+        .map(|tx_ref| transaction_path(client, tx_ref))
+        .collect();
+    let resolved: Vec<String> = paths
+        .iter()
+        .filter_map(|path| path.as_ref().ok().cloned())
+        .collect();
+    let mut fetched = client.get_json_batch(&resolved).into_iter();
+
+    let items: Vec<BatchItem> = refs
+        .into_iter()
+        .zip(paths)
+        .map(|(input, path)| {
+            let result = match path {
+                Ok(_) => fetched
+                    .next()
+                    .ok_or_else(|| anyhow!("missing batch result"))
+                    .and_then(|fetched| Ok(fetched?)),
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(transaction) => BatchItem {
+                    input,
+                    transaction: Some(transaction),
+                    error: None,
+                },
+                Err(err) => BatchItem {
+                    input,
+                    transaction: None,
+                    error: Some(format!("{err:#}")),
+                },
+            }
+        })
+        .collect();
+    crate::print_serialized(&items)
+}
+
+/// Submits one signed transaction, or each of a JSON array of them in order.
+fn run_tx_submit(client: &AptosClient, args: &TxSubmitArgs) -> Result<()> {
+    let input = read_json_input(
+        args.file.as_deref(),
+        "failed to parse signed transaction JSON from stdin",
+    )?;
+    let Value::Array(txns) = input else {
+        let value = client.post_json("/transactions", &input)?;
+        return crate::print_pretty_json(&value);
+    };
+
+    let mut items = Vec::with_capacity(txns.len());
+    for (index, txn) in txns.iter().enumerate() {
+        match client.post_json("/transactions", txn) {
+            Ok(result) => items.push(SubmitItem {
+                index,
+                result: Some(result),
+                error: None,
+            }),
+            Err(err) => {
+                items.push(SubmitItem {
+                    index,
+                    result: None,
+                    error: Some(format!("{:#}", anyhow::Error::from(err))),
+                });
+                if args.fail_fast {
+                    crate::print_serialized(&items)?;
+                    return Err(anyhow!(
+                        "submission {index} failed; stopped with {} of {} transaction(s) not submitted",
+                        txns.len() - index - 1,
+                        txns.len()
+                    ));
+                }
+            }
+        }
+    }
+    crate::print_serialized(&items)?;
+
+    let failed = items.iter().filter(|item| item.error.is_some()).count();
+    if failed > 0 {
+        return Err(anyhow!("{failed} of {} submission(s) failed", items.len()));
+    }
+    Ok(())
+}
+
+fn read_batch_refs(path: &Path) -> Result<Vec<String>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read batch input {}", path.display()))?;
+    if contents.trim_start().starts_with('[') {
+        let values: Vec<Value> = serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse batch input {} as JSON", path.display()))?;
+        return values
+            .iter()
+            .map(|value| {
+                let tx_ref = value_to_string(value);
+                if tx_ref.is_empty() {
+                    Err(anyhow!(
+                        "batch input entries must be strings or numbers, got {value}"
+                    ))
+                } else {
+                    Ok(tx_ref)
+                }
+            })
+            .collect();
+    }
+
+    Ok(contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_owned)
+        .collect())
+}
+
+fn run_tx_encode(client: &AptosClient, args: &TxInputArgs) -> Result<()> {
+    let txn = read_json_input(
+        args.file.as_deref(),
+        "failed to parse unsigned transaction JSON from stdin",
+    )?;
     let encoded = client.post_json("/transactions/encode_submission", &txn)?;
     crate::print_pretty_json(&encoded)
 }
 
+/// Passes typed payloads through, checking `script_payload` bytecode (with or
+/// without `0x`) and defaulting its argument lists as `compose` would emit them.
+fn normalize_typed_payload(payload: &Value) -> Result<Value> {
+    if payload.get("type").and_then(Value::as_str) != Some("script_payload") {
+        return Ok(payload.clone());
+    }
+
+    let bytecode = get_nested_string(payload, &["code", "bytecode"]);
+    let bytecode = bytecode.strip_prefix("0x").unwrap_or(&bytecode);
+    if bytecode.is_empty() {
+        return Err(anyhow!("script_payload is missing `code.bytecode`"));
+    }
+    hex::decode(bytecode).context("script_payload `code.bytecode` is not valid hex")?;
+
+    let list = |field: &str| payload.get(field).cloned().unwrap_or_else(|| json!([]));
+    Ok(json!({
+        "type": "script_payload",
+        "code": {"bytecode": format!("0x{bytecode}")},
+        "type_arguments": list("type_arguments"),
+        "arguments": list("arguments")
+    }))
+}
+
 fn run_tx_simulate(client: &AptosClient, args: &TxSimulateArgs) -> Result<()> {
-    let stdin_value = read_json_from_stdin("failed to parse payload JSON from stdin")?;
-    let payload = normalize_simulation_payload(&stdin_value)?;
+    let input = read_json_input(
+        args.file.as_deref(),
+        "failed to parse payload JSON from stdin",
+    )?;
+    let payload = normalize_simulation_payload(&input)?;
 
     let account = client
         .get_json(&format!("/accounts/{}", args.sender))
@@ -186,11 +553,7 @@ fn run_tx_simulate(client: &AptosClient, args: &TxSimulateArgs) -> Result<()> {
     let gas_price = client
         .get_json("/estimate_gas_price")
         .context("failed to fetch gas price estimate")?;
-    let gas_unit_price = first_non_empty_string(&[
-        get_nested_string(&gas_price, &["gas_estimate"]),
-        get_nested_string(&gas_price, &["gas_unit_price"]),
-    ])
-    .unwrap_or_else(|| "100".to_owned());
+    let gas_unit_price = gas_unit_price_estimate(&gas_price).unwrap_or_else(|| "100".to_owned());
 
     let ledger = client
         .get_json("/")
@@ -199,25 +562,76 @@ fn run_tx_simulate(client: &AptosClient, args: &TxSimulateArgs) -> Result<()> {
         .ok_or_else(|| anyhow!("failed to parse ledger timestamp"))?;
     let expiration_timestamp_secs = (ledger_timestamp_micros / 1_000_000 + 600).to_string();
 
-    let simulate_request = json!({
-        "sender": args.sender,
-        "sequence_number": sequence_number,
-        "max_gas_amount": "200000",
-        "gas_unit_price": gas_unit_price,
-        "expiration_timestamp_secs": expiration_timestamp_secs,
-        "payload": payload,
-        "signature": {"type": "no_account_signature"}
-    });
-
-    let response = client
-        .post_json("/transactions/simulate", &simulate_request)
-        .context("failed to simulate transaction")?;
+    let simulate = |max_gas_amount: u64| -> Result<Value> {
+        let simulate_request = json!({
+            "sender": args.sender,
+            "sequence_number": sequence_number,
+            "max_gas_amount": max_gas_amount.to_string(),
+            "gas_unit_price": gas_unit_price,
+            "expiration_timestamp_secs": expiration_timestamp_secs,
+            "payload": payload,
+            "signature": {"type": "no_account_signature"}
+        });
+        let mut response = client
+            .post_json("/transactions/simulate", &simulate_request)
+            .context("failed to simulate transaction")?;
+        Ok(match response.as_array_mut() {
+            Some(results) if !results.is_empty() => results.swap_remove(0),
+            _ => response,
+        })
+    };
 
-    if let Some(first) = response.as_array().and_then(|arr| arr.first()) {
-        return crate::print_pretty_json(first);
+    let first_pass_max_gas = args.max_gas.unwrap_or(if args.estimate_max_gas {
+        ESTIMATE_FIRST_PASS_MAX_GAS
+    } else {
+        DEFAULT_SIMULATE_MAX_GAS
+    });
+    let mut result = simulate(first_pass_max_gas)?;
+    if args.estimate_max_gas {
+        // A failed first pass (often out of gas) says nothing about the real cost.
+        if result.get("success").and_then(Value::as_bool) != Some(true) {
+            return Err(anyhow!(
+                "first simulation pass failed ({}) with max_gas_amount {}; if it ran out of gas, retry with a higher --max-gas",
+                get_nested_string(&result, &["vm_status"]),
+                first_pass_max_gas
+            ));
+        }
+        let gas_used = parse_u64(result.get("gas_used").unwrap_or(&Value::Null))
+            .ok_or_else(|| anyhow!("simulation result missing `gas_used`"))?;
+        // gas_used * 1.2, rounded up.
+        let bounded = gas_used.saturating_mul(6).div_ceil(5).max(1);
+        result = simulate(bounded)?;
+    }
+    if args.gas_profile {
+        let profile = gas_profile(&result, args.verbose)?;
+        return crate::print_serialized(&profile);
     }
 
-    crate::print_pretty_json(&response)
+    crate::print_pretty_json(&result)
+}
+
+fn gas_profile(result: &Value, verbose: bool) -> Result<GasProfile> {
+    let field = |name: &str| {
+        parse_u64(result.get(name).unwrap_or(&Value::Null))
+            .ok_or_else(|| anyhow!("simulation result missing `{name}`"))
+    };
+    let gas_used = field("gas_used")?;
+    let gas_unit_price = field("gas_unit_price")?;
+    let fee_octas = (u128::from(gas_used) * u128::from(gas_unit_price)).to_string();
+
+    Ok(GasProfile {
+        success: result
+            .get("success")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        vm_status: get_nested_string(result, &["vm_status"]),
+        gas_used,
+        gas_unit_price,
+        max_gas_amount: field("max_gas_amount")?,
+        fee_apt: format_amount(&fee_octas, APT_DECIMALS, None),
+        fee_octas,
+        simulation: verbose.then(|| result.clone()),
+    })
 }
 
 fn run_tx_compose(rpc_url: &str, args: &TxComposeArgs) -> Result<()> {
@@ -238,6 +652,18 @@ fn run_tx_compose(rpc_url: &str, args: &TxComposeArgs) -> Result<()> {
     if args.emit_script_payload {
         command.arg("--emit-script-payload");
     }
+    if let Some(emit) = &args.emit {
+        command.arg("--emit").arg(emit);
+    }
+    if args.explain {
+        command.arg("--explain");
+    }
+    if let Some(dir) = &args.modules_dir {
+        command.arg("--modules-dir").arg(dir);
+    }
+    if args.validate_only {
+        command.arg("--validate-only");
+    }
 
     let status = command
         .stdin(Stdio::inherit())
@@ -262,13 +688,29 @@ fn read_json_from_stdin(error_message: &str) -> Result<Value> {
     serde_json::from_reader(reader.lock()).context(error_message.to_owned())
 }
 
+/// Reads JSON from `file` when given, otherwise from stdin.
+fn read_json_input(file: Option<&Path>, stdin_error_message: &str) -> Result<Value> {
+    match file {
+        Some(path) => read_json_file(path),
+        None => read_json_from_stdin(stdin_error_message),
+    }
+}
+
+fn read_json_file(path: &Path) -> Result<Value> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse JSON from {}", path.display()))
+}
+
 fn normalize_simulation_payload(input: &Value) -> Result<Value> {
-    if let Some(payload) = input.get("payload") {
-        return Ok(payload.clone());
+    // `compose --emit both` wraps the payload next to the raw script bytes.
+    if let Some(payload) = input.get("payload").or_else(|| input.get("script_payload")) {
+        return normalize_typed_payload(payload);
     }
 
     if input.get("type").is_some() {
-        return Ok(input.clone());
+        return normalize_typed_payload(input);
     }
 
     let function = get_nested_string(input, &["function"]);
@@ -308,17 +750,60 @@ fn run_tx_trace(client: &AptosClient, rpc_url: &str, args: &TxTraceArgs) -> Resu
             local_tracer.as_ref().map(String::as_str),
         )?
     } else {
-        fetch_trace_from_external_tracer(chain_id, &tx_hash)?
+        fetch_trace_from_external_tracer(client.http_config(), chain_id, &tx_hash)?
+    };
+    let mut value = match serde_json::from_str::<Value>(&trace_json) {
+        Ok(value) => value,
+        // Deeply nested traces can exceed serde_json's recursion limit for `Value`.
+        // Fall back to raw JSON so tracing still succeeds.
+        Err(_) if args.render == TraceRender::Json => {
+            writeln!(crate::pager::stdout(), "{trace_json}")?;
+            return Ok(());
+        }
+        Err(err) => {
+            return Err(anyhow!(
+                "failed to parse trace for rendering ({err}); deeply nested traces can only be printed with `--render json`"
+            ));
+        }
+    };
+    if let Some(depth) = args.depth {
+        truncate_call_tree(&mut value, depth);
+    }
+
+    let render = match args.render {
+        TraceRender::Json => return crate::print_pretty_json(&value),
+        TraceRender::Tree => render_tree,
+        TraceRender::Flamegraph => render_folded,
+    };
+    let root: CallFrame = serde_json::from_value(value).context("unexpected call trace format")?;
+    write!(crate::pager::stdout(), "{}", render(&root))?;
+    Ok(())
+}
+
+/// Replaces `calls` below `depth` with a single `{truncated, omitted}` marker.
+fn truncate_call_tree(call: &mut Value, depth: usize) {
+    let Some(calls) = call.get_mut("calls").and_then(Value::as_array_mut) else {
+        return;
     };
-    match serde_json::from_str::<Value>(&trace_json) {
-        Ok(value) => crate::print_pretty_json(&value),
-        Err(_) => {
-            // Deeply nested traces can exceed serde_json's recursion limit for `Value`.
-            // Fall back to raw JSON so tracing still succeeds.
-            println!("{trace_json}");
-            Ok(())
+    if depth > 0 {
+        for child in calls.iter_mut() {
+            truncate_call_tree(child, depth - 1);
         }
+        return;
+    }
+    if calls.is_empty() {
+        return;
     }
+
+    let omitted: usize = calls.iter().map(count_calls).sum();
+    *calls = vec![json!({ "truncated": true, "omitted": omitted })];
+}
+
+fn count_calls(call: &Value) -> usize {
+    1 + call
+        .get("calls")
+        .and_then(Value::as_array)
+        .map_or(0, |calls| calls.iter().map(count_calls).sum())
 }
 
 fn resolve_trace_tx_hash(client: &AptosClient, version_or_hash: &str) -> Result<String> {
@@ -327,9 +812,22 @@ fn resolve_trace_tx_hash(client: &AptosClient, version_or_hash: &str) -> Result<
         return Err(anyhow!("missing transaction version/hash for trace"));
     }
 
-    if tx_ref.parse::<u64>().is_ok() {
+    let version = match resolve_relative_version(client, tx_ref)? {
+        Some(version) => Some(version),
+        None => tx_ref.parse::<u64>().ok(),
+    };
+    if let Some(version) = version {
+        let path = format!("/transactions/by_version/{version}");
+        // `hash` sits near the top of the response, so avoid downloading the
+        // full (possibly very large) transaction body when we can.
+        if let Some(hash) = client
+            .get_top_level_string_field(&path, "hash")
+            .context("failed to fetch transaction by version for trace")?
+        {
+            return Ok(strip_hex_prefix(&hash).to_owned());
+        }
         let tx = client
-            .get_json(&format!("/transactions/by_version/{tx_ref}"))
+            .get_json(&path)
             .context("failed to fetch transaction by version for trace")?;
         let hash = tx
             .get("hash")
@@ -343,7 +841,7 @@ fn resolve_trace_tx_hash(client: &AptosClient, version_or_hash: &str) -> Result<
 
 fn resolve_trace_chain_id(client: &AptosClient) -> Result<u16> {
     let ledger = client
-        .get_json("/")
+        .get_json_cached("/")
         .context("failed to fetch ledger info for trace chain id")?;
     let chain_id_u64 = parse_u64(ledger.get("chain_id").unwrap_or(&Value::Null))
         .ok_or_else(|| anyhow!("failed to parse `chain_id` from ledger response"))?;
@@ -365,8 +863,8 @@ fn run_local_trace_with_aptos_tracer(
     let mut command = Command::new(&tracer_bin);
     let output = command
         .arg("rest")
-        .arg(rpc_url.trim().to_owned())
-        .arg(tx_hash.to_owned())
+        .arg(rpc_url.trim())
+        .arg(tx_hash)
         .arg(chain_id.to_string())
         .stdin(Stdio::null())
         .output()
@@ -399,17 +897,28 @@ fn run_local_trace_with_aptos_tracer(
     Ok(trace_json)
 }
 
-fn fetch_trace_from_external_tracer(chain_id: u16, tx_hash: &str) -> Result<String> {
+fn fetch_trace_from_external_tracer(
+    http_config: &HttpConfig,
+    chain_id: u16,
+    tx_hash: &str,
+) -> Result<String> {
     let sentio_url = build_sentio_call_trace_url(chain_id, tx_hash);
-    fetch_trace_from_url(&sentio_url)
+    fetch_trace_from_url(http_config, &sentio_url)
         .with_context(|| format!("failed to fetch trace from Sentio API `{}`", sentio_url))
 }
 
-fn fetch_trace_from_url(url: &str) -> Result<String> {
-    let http = reqwest::blocking::Client::builder()
-        .timeout(DEFAULT_TRACER_REQUEST_TIMEOUT)
-        .build()
-        .context("failed to build HTTP client for trace endpoint")?;
+fn fetch_trace_from_url(http_config: &HttpConfig, url: &str) -> Result<String> {
+    // Traces can take minutes to build, so only an explicit timeout overrides this one.
+    let http = HttpConfig {
+        timeout: Some(
+            http_config
+                .timeout
+                .unwrap_or(DEFAULT_TRACER_REQUEST_TIMEOUT),
+        ),
+        ..http_config.clone()
+    }
+    .build_client()
+    .context("failed to build HTTP client for trace endpoint")?;
 
     let response = http
         .get(url)
@@ -445,24 +954,192 @@ fn strip_hex_prefix(value: &str) -> &str {
 }
 
 fn run_tx_balance_change(client: &AptosClient, args: &TxBalanceChangeArgs) -> Result<()> {
-    let tx = get_transaction(client, args.version_or_hash.as_deref())?;
+    let tx = get_transaction(
+        client,
+        args.version_or_hash.as_deref(),
+        args.file.as_deref(),
+    )?;
     if tx.get("type").and_then(Value::as_str).unwrap_or_default() != "user_transaction" {
         return Err(anyhow!("not a user transaction"));
     }
 
     let version = parse_u64(tx.get("version").unwrap_or(&Value::Null)).unwrap_or(0);
     let mut store_info = extract_transfer_store_info_from_tx(&tx);
-    let events = build_balance_change_events(&tx, &mut store_info, client, version);
+    let mut events = build_balance_change_events(
+        &tx,
+        &mut store_info,
+        client,
+        version,
+        args.strict,
+        !args.no_gas,
+    )?;
+
+    let resolver = AssetResolver::new(client);
+    let prettify = |asset: &str, amount: &mut String, symbol: &mut Option<String>| {
+        let metadata = resolver.resolve(asset);
+        *amount = format_amount(amount, metadata.decimals, args.precision);
+        *symbol = Some(metadata.symbol);
+    };
 
     if args.aggregate {
-        let aggregated = aggregate_events(&events);
+        let mut aggregated = aggregate_events(&events);
+        aggregated.retain(|change| {
+            let amount = BigInt::from_str(&change.amount).unwrap_or_default();
+            !(args.net_only && amount == BigInt::from(0))
+                && args
+                    .min_abs
+                    .as_ref()
+                    .is_none_or(|min| amount.magnitude() >= min.magnitude())
+        });
+        if args.pretty {
+            for change in &mut aggregated {
+                prettify(&change.asset, &mut change.amount, &mut change.symbol);
+            }
+        }
         return crate::print_serialized(&aggregated);
     }
 
+    if args.pretty {
+        for change in &mut events {
+            prettify(&change.asset, &mut change.amount, &mut change.symbol);
+        }
+    }
     crate::print_serialized(&events)
 }
 
-fn get_transaction(client: &AptosClient, version_or_hash: Option<&str>) -> Result<Value> {
+/// Polls the transaction until it is no longer pending. A 404 is treated as
+/// "not visible yet", since a freshly submitted hash can lag behind the node.
+fn run_tx_wait(client: &AptosClient, args: &TxWaitArgs) -> Result<()> {
+    let path = transaction_path(client, &args.version_or_hash)?;
+    let timeout = Duration::from_secs(args.wait_secs);
+    let interval = Duration::from_millis(args.interval_ms);
+    let deadline = Instant::now() + timeout;
+
+    let value = loop {
+        let last_seen = match client.get_json(&path) {
+            Ok(value)
+                if value.get("type").and_then(Value::as_str) != Some("pending_transaction") =>
+            {
+                break value;
+            }
+            Ok(_) => "pending",
+            Err(AptosError::NotFound(_)) => "not found",
+            Err(err) => return Err(err.into()),
+        };
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(anyhow!(
+                "transaction {} not committed after {}s (last seen: {last_seen})",
+                args.version_or_hash,
+                args.wait_secs
+            ));
+        }
+        thread::sleep(interval.min(remaining));
+    };
+
+    crate::print_pretty_json(&value)?;
+    if value.get("success").and_then(Value::as_bool) == Some(false) {
+        let vm_status = get_nested_string(&value, &["vm_status"]);
+        return Err(anyhow!("transaction failed on-chain: {vm_status}"));
+    }
+    Ok(())
+}
+
+fn run_tx_export(client: &AptosClient, rpc_url: &str, args: &TxExportArgs) -> Result<()> {
+    let tx = client.get_json(&transaction_path(client, args.version_or_hash.trim())?)?;
+    let version = parse_u64(tx.get("version").unwrap_or(&Value::Null))
+        .ok_or_else(|| anyhow!("transaction response missing `version` field"))?;
+    let hash = get_nested_string(&tx, &["hash"]);
+    let chain_id = resolve_trace_chain_id(client)?;
+
+    fs::create_dir_all(args.out.join("resources"))
+        .with_context(|| format!("failed to create export directory {}", args.out.display()))?;
+    write_bundle_file(&args.out, "transaction.json", &tx)?;
+
+    let (trace, trace_error) = if args.no_trace {
+        (None, None)
+    } else {
+        let tx_hash = strip_hex_prefix(&hash);
+        let trace_json = match args.local_tracer.as_ref() {
+            Some(local_tracer) => run_local_trace_with_aptos_tracer(
+                rpc_url,
+                chain_id,
+                tx_hash,
+                local_tracer.as_deref(),
+            ),
+            None => fetch_trace_from_external_tracer(client.http_config(), chain_id, tx_hash),
+        };
+        // A missing trace still leaves a useful bundle; record why instead of failing.
+        match trace_json {
+            Ok(trace_json) => {
+                let path = args.out.join("trace.json");
+                fs::write(&path, trace_json)
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+                (Some("trace.json".to_owned()), None)
+            }
+            Err(err) => {
+                eprintln!("warning: trace not exported: {err:#}");
+                (None, Some(format!("{err:#}")))
+            }
+        }
+    };
+
+    let mut resources = BTreeMap::new();
+    for address in changed_addresses(&tx) {
+        let path = with_optional_ledger_version(
+            client,
+            &format!("/accounts/{address}/resources"),
+            Some(version),
+        )?;
+        let value = client
+            .get_json(&path)
+            .with_context(|| format!("failed to read resources of {address} at {version}"))?;
+        let file = format!("resources/{address}.json");
+        write_bundle_file(&args.out, &file, &value)?;
+        resources.insert(address, file);
+    }
+
+    let manifest = ExportManifest {
+        aptly_version: env!("CARGO_PKG_VERSION"),
+        chain_id,
+        version,
+        hash,
+        transaction: "transaction.json".to_owned(),
+        trace,
+        trace_error,
+        resources,
+    };
+    write_bundle_file(&args.out, "manifest.json", &manifest)?;
+    crate::print_serialized(&manifest)
+}
+
+/// Every account address that appears in the transaction's write set.
+fn changed_addresses(tx: &Value) -> BTreeSet<String> {
+    tx.get("changes")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|change| change.get("address").and_then(Value::as_str))
+        .map(str::to_owned)
+        .collect()
+}
+
+fn write_bundle_file(dir: &Path, name: &str, value: &impl Serialize) -> Result<()> {
+    let path = dir.join(name);
+    let json = serde_json::to_vec_pretty(value).context("failed to serialize bundle file")?;
+    fs::write(&path, json).with_context(|| format!("failed to write {}", path.display()))
+}
+
+fn get_transaction(
+    client: &AptosClient,
+    version_or_hash: Option<&str>,
+    file: Option<&Path>,
+) -> Result<Value> {
+    if let Some(path) = file {
+        return read_json_file(path);
+    }
+
     if !io::stdin().is_terminal() {
         let mut input = String::new();
         io::stdin()
@@ -476,11 +1153,142 @@ fn get_transaction(client: &AptosClient, version_or_hash: Option<&str>) -> Resul
     }
 
     let tx_ref = version_or_hash.ok_or_else(|| anyhow!("no transaction provided"))?;
+    Ok(client.get_json(&transaction_path(client, tx_ref)?)?)
+}
+
+fn run_tx_decode_payload(client: &AptosClient, args: &TxDecodePayloadArgs) -> Result<()> {
+    let tx = client.get_json(&transaction_path(client, &args.version_or_hash)?)?;
+    let Some(payload) = tx.get("payload") else {
+        let tx_type = tx.get("type").and_then(Value::as_str).unwrap_or("unknown");
+        return Err(anyhow!("transaction has no payload (type `{tx_type}`)"));
+    };
+    let payload_type = payload
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown");
+    if payload_type != "entry_function_payload" {
+        return Err(anyhow!(
+            "unsupported payload type `{payload_type}`; decode-payload only handles entry_function_payload"
+        ));
+    }
+
+    let function = get_nested_string(payload, &["function"]);
+    let type_arguments: Vec<String> = payload
+        .get("type_arguments")
+        .and_then(Value::as_array)
+        .map(|types| types.iter().map(value_to_string).collect())
+        .unwrap_or_default();
+    let arguments = payload
+        .get("arguments")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+
+    // The ABI as of the transaction, so a later module upgrade cannot
+    // relabel or reshape its arguments. Pending transactions have no version.
+    let version = tx.get("version").and_then(parse_u64);
+    let abi_function = fetch_function_abi(client, &function, version)?;
+    // Signers come from the transaction, not the argument list.
+    let params: Vec<String> = abi_function
+        .get("params")
+        .and_then(Value::as_array)
+        .map(|params| params.iter().map(value_to_string).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|param| !matches!(param.as_str(), "signer" | "&signer"))
+        .map(|param| substitute_type_parameters(&param, &type_arguments))
+        .collect();
+    if params.len() != arguments.len() {
+        return Err(anyhow!(
+            "ABI of {function} declares {} non-signer parameter(s) but the payload has {} argument(s)",
+            params.len(),
+            arguments.len()
+        ));
+    }
+
+    let decoded: Vec<Value> = params
+        .iter()
+        .zip(arguments)
+        .enumerate()
+        .map(|(index, (ty, value))| {
+            let mut entry = json!({ "index": index, "type": ty });
+            if let Some(human) = decode_argument(ty, &value) {
+                entry["decoded"] = human;
+            }
+            entry["value"] = value;
+            entry
+        })
+        .collect();
+
+    crate::print_pretty_json(&json!({
+        "function": function,
+        "type_arguments": type_arguments,
+        "arguments": decoded
+    }))
+}
+
+/// A readable form of an entry function argument, when its type has one:
+/// UTF-8 text for `vector<u8>`, the inner value of an `Option`, and the
+/// address of an `Object`.
+fn decode_argument(ty: &str, value: &Value) -> Option<Value> {
+    if ty == "vector<u8>" {
+        let bytes = hex::decode(value.as_str()?.strip_prefix("0x")?).ok()?;
+        let text = String::from_utf8(bytes).ok()?;
+        return (!text.is_empty() && !text.chars().any(char::is_control))
+            .then_some(Value::String(text));
+    }
+    if ty.starts_with("0x1::option::Option<") {
+        let mut items = value.get("vec")?.as_array()?.clone();
+        return match items.len() {
+            0 => Some(Value::Null),
+            1 => items.pop(),
+            _ => None,
+        };
+    }
+    if ty.starts_with("0x1::object::Object<") {
+        return value.get("inner").cloned();
+    }
+    None
+}
+
+fn transaction_path(client: &AptosClient, tx_ref: &str) -> Result<String> {
+    if let Some(version) = resolve_relative_version(client, tx_ref)? {
+        return Ok(format!("/transactions/by_version/{version}"));
+    }
     if tx_ref.parse::<u64>().is_ok() {
-        return client.get_json(&format!("/transactions/by_version/{tx_ref}"));
+        return Ok(format!("/transactions/by_version/{tx_ref}"));
     }
+    Ok(format!("/transactions/by_hash/{tx_ref}"))
+}
+
+/// Resolves `latest` / `latest-N` against the node's ledger info. Returns
+/// `None` for any other reference.
+fn resolve_relative_version(client: &AptosClient, tx_ref: &str) -> Result<Option<u64>> {
+    let Some(rest) = tx_ref.trim().strip_prefix("latest") else {
+        return Ok(None);
+    };
+    let back = match rest.strip_prefix('-') {
+        Some(n) => n
+            .parse::<u64>()
+            .with_context(|| format!("invalid relative version `{tx_ref}`, expected latest-<N>"))?,
+        None if rest.is_empty() => 0,
+        None => return Ok(None),
+    };
 
-    client.get_json(&format!("/transactions/by_hash/{tx_ref}"))
+    let ledger = client.get_json_cached("/")?;
+    let latest = parse_u64(ledger.get("ledger_version").unwrap_or(&Value::Null))
+        .ok_or_else(|| anyhow!("ledger info missing `ledger_version` field"))?;
+    let oldest =
+        parse_u64(ledger.get("oldest_ledger_version").unwrap_or(&Value::Null)).unwrap_or(0);
+    let version = latest
+        .checked_sub(back)
+        .filter(|version| *version >= oldest)
+        .ok_or_else(|| {
+            anyhow!(
+                "`{tx_ref}` is outside the node's available range (versions {oldest}..={latest})"
+            )
+        })?;
+    Ok(Some(version))
 }
 
 fn build_balance_change_events(
@@ -488,32 +1296,49 @@ fn build_balance_change_events(
     store_info: &mut HashMap<String, TransferStoreMetadata>,
     client: &AptosClient,
     version: u64,
-) -> Vec<BalanceChange> {
+    strict: bool,
+    include_gas: bool,
+) -> Result<Vec<BalanceChange>> {
     let mut events = Vec::new();
 
-    let gas_used = parse_bigint(tx.get("gas_used").unwrap_or(&Value::Null));
-    let gas_unit_price = parse_bigint(tx.get("gas_unit_price").unwrap_or(&Value::Null));
-    let gas_fee = gas_used * gas_unit_price;
+    let amount_field = |field: &str| {
+        let value = tx.get(field).unwrap_or(&Value::Null);
+        check_strict_amount(strict, version, field, &value_to_string(value))?;
+        Ok::<_, anyhow::Error>(parse_bigint(value))
+    };
+    let gas_fee = if include_gas {
+        amount_field("gas_used")? * amount_field("gas_unit_price")?
+    } else {
+        BigInt::from(0)
+    };
     if gas_fee > BigInt::from(0) {
-        let sender = tx
-            .get("sender")
-            .and_then(Value::as_str)
-            .unwrap_or_default()
-            .to_owned();
-        let apt_store = find_sender_apt_store(tx, &sender);
+        let fee_payer = fee_payer_address(tx);
+        let payer = fee_payer.clone().unwrap_or_else(|| {
+            tx.get("sender")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_owned()
+        });
+        let apt_store = find_owner_apt_store(tx, &payer);
         events.push(BalanceChange {
             event_type: "gas_fee".to_owned(),
-            account: sender,
+            account: payer,
             fungible_store: apt_store,
             asset: "0xa".to_owned(),
             amount: gas_fee.to_string(),
+            symbol: None,
+            fee_payer,
         });
     }
 
     let Some(tx_events) = tx.get("events").and_then(Value::as_array) else {
-        return events;
+        return Ok(events);
     };
 
+    // Coin events are resolved after the FA events so a transfer reported by
+    // both families during the coin-to-FA migration is only counted once.
+    let mut fa_events = Vec::new();
+    let mut coin_events = Vec::new();
     for event in tx_events {
         let Some(event_type) = event.get("type").and_then(Value::as_str) else {
             continue;
@@ -521,14 +1346,28 @@ fn build_balance_change_events(
         let normalized = match event_type {
             "0x1::fungible_asset::Withdraw" => "withdraw",
             "0x1::fungible_asset::Deposit" => "deposit",
-            _ => continue,
+            _ => {
+                if let Some(change) = coin_balance_change(tx, event, event_type, strict, version)? {
+                    check_strict_amount(
+                        strict,
+                        version,
+                        &format!("{event_type} amount"),
+                        &change.amount,
+                    )?;
+                    coin_events.push(change);
+                }
+                continue;
+            }
         };
 
         let store = get_nested_string(event, &["data", "store"]);
         let amount = get_nested_string(event, &["data", "amount"]);
         if store.is_empty() || amount.is_empty() {
+            let missing = if store.is_empty() { "store" } else { "amount" };
+            check_strict_present(strict, version, event_type, missing)?;
             continue;
         }
+        check_strict_amount(strict, version, &format!("{event_type} amount"), &amount)?;
 
         if !store_info.contains_key(&store) {
             let metadata = query_transfer_store_info(client, &store, version);
@@ -536,16 +1375,199 @@ fn build_balance_change_events(
         }
         let metadata = store_info.get(&store).cloned().unwrap_or_default();
 
-        events.push(BalanceChange {
+        fa_events.push(BalanceChange {
             event_type: normalized.to_owned(),
             account: metadata.owner,
             fungible_store: store,
             asset: metadata.asset,
             amount,
+            symbol: None,
+            fee_payer: None,
         });
     }
 
-    events
+    let unique_coin_events = drop_paired_coin_events(&fa_events, coin_events, |coin_type| {
+        paired_fa_metadata(client, coin_type, Some(version))
+    })?;
+
+    events.extend(fa_events);
+    events.extend(unique_coin_events);
+    Ok(events)
+}
+
+/// Drops coin events already reported by an FA event for the coin's paired
+/// metadata. `paired_metadata` is only asked about coin types that have a
+/// matching FA transfer, once per type; its errors are returned rather than
+/// risking a double count.
+fn drop_paired_coin_events(
+    fa_events: &[BalanceChange],
+    coin_events: Vec<BalanceChange>,
+    mut paired_metadata: impl FnMut(&str) -> Result<Option<String>>,
+) -> Result<Vec<BalanceChange>> {
+    let mut matched = vec![false; fa_events.len()];
+    let mut paired_assets: HashMap<String, Option<String>> = HashMap::new();
+    let mut unique_coin_events = Vec::new();
+    for change in coin_events {
+        if fa_events.iter().any(|fa| same_transfer(fa, &change)) {
+            let paired = match paired_assets.get(&change.asset) {
+                Some(paired) => paired.clone(),
+                None => {
+                    let paired = paired_metadata(&change.asset)?;
+                    paired_assets.insert(change.asset.clone(), paired.clone());
+                    paired
+                }
+            };
+            if let Some(paired) = paired.as_deref() {
+                let duplicate = fa_events.iter().zip(&matched).position(|(fa, done)| {
+                    !done
+                        && same_transfer(fa, &change)
+                        && normalize_hex_address(&fa.asset) == normalize_hex_address(paired)
+                });
+                if let Some(index) = duplicate {
+                    matched[index] = true;
+                    continue;
+                }
+            }
+        }
+        unique_coin_events.push(change);
+    }
+    Ok(unique_coin_events)
+}
+
+/// Deposits into `account` in `tx` as `(asset, raw amount)`, read the same
+/// way `tx balance-change` reads them.
+pub(crate) fn deposits_to(
+    client: &AptosClient,
+    tx: &Value,
+    account: &str,
+) -> Result<Vec<(String, String)>> {
+    let version = parse_u64(tx.get("version").unwrap_or(&Value::Null)).unwrap_or(0);
+    let mut store_info = extract_transfer_store_info_from_tx(tx);
+    let events = build_balance_change_events(tx, &mut store_info, client, version, false, false)?;
+    let account = normalize_hex_address(account);
+    Ok(events
+        .into_iter()
+        .filter(|change| {
+            change.event_type == "deposit" && normalize_hex_address(&change.account) == account
+        })
+        .map(|change| (change.asset, change.amount))
+        .collect())
+}
+
+/// Whether two changes move the same amount in the same direction for the
+/// same account, ignoring the asset representation.
+fn same_transfer(a: &BalanceChange, b: &BalanceChange) -> bool {
+    a.event_type == b.event_type
+        && a.amount == b.amount
+        && normalize_hex_address(&a.account) == normalize_hex_address(&b.account)
+}
+
+/// Maps a legacy coin event to a `BalanceChange` keyed by coin type:
+/// handle-based `0x1::coin::WithdrawEvent`/`DepositEvent` (coin type taken
+/// from the account's `CoinStore` in the write set), and the module events
+/// `0x1::coin::CoinWithdraw`/`CoinDeposit`. With `strict`, a coin event that
+/// cannot be read is an error rather than skipped.
+fn coin_balance_change(
+    tx: &Value,
+    event: &Value,
+    event_type: &str,
+    strict: bool,
+    version: u64,
+) -> Result<Option<BalanceChange>> {
+    let (normalized, account, coin_type) = match event_type {
+        "0x1::coin::WithdrawEvent" | "0x1::coin::DepositEvent" => {
+            let (normalized, handle) = if event_type == "0x1::coin::WithdrawEvent" {
+                ("withdraw", "withdraw_events")
+            } else {
+                ("deposit", "deposit_events")
+            };
+            let account = get_nested_string(event, &["guid", "account_address"]);
+            let creation_number = get_nested_string(event, &["guid", "creation_number"]);
+            let coin_type = coin_type_for_event_handle(tx, &account, handle, &creation_number)
+                .unwrap_or_default();
+            (normalized, account, coin_type)
+        }
+        "0x1::coin::CoinWithdraw" | "0x1::coin::CoinDeposit" => {
+            let normalized = if event_type == "0x1::coin::CoinWithdraw" {
+                "withdraw"
+            } else {
+                "deposit"
+            };
+            (
+                normalized,
+                get_nested_string(event, &["data", "account"]),
+                get_nested_string(event, &["data", "coin_type"]),
+            )
+        }
+        _ => return Ok(None),
+    };
+
+    let amount = get_nested_string(event, &["data", "amount"]);
+    let missing = [
+        ("account", &account),
+        ("coin type", &coin_type),
+        ("amount", &amount),
+    ]
+    .into_iter()
+    .find_map(|(field, value)| value.is_empty().then_some(field));
+    if let Some(field) = missing {
+        check_strict_present(strict, version, event_type, field)?;
+        return Ok(None);
+    }
+    Ok(Some(BalanceChange {
+        event_type: normalized.to_owned(),
+        account,
+        fungible_store: String::new(),
+        asset: coin_type,
+        amount,
+        symbol: None,
+        fee_payer: None,
+    }))
+}
+
+/// Finds the coin type of the `CoinStore<T>` at `account` whose `handle`
+/// event handle has `creation_number`.
+fn coin_type_for_event_handle(
+    tx: &Value,
+    account: &str,
+    handle: &str,
+    creation_number: &str,
+) -> Option<String> {
+    let account = normalize_hex_address(account);
+    tx.get("changes")?.as_array()?.iter().find_map(|change| {
+        if change.get("type").and_then(Value::as_str) != Some("write_resource")
+            || normalize_hex_address(&get_nested_string(change, &["address"])) != account
+        {
+            return None;
+        }
+        let resource_type = get_nested_string(change, &["data", "type"]);
+        let coin_type = resource_type
+            .strip_prefix("0x1::coin::CoinStore<")?
+            .strip_suffix('>')?;
+        let number = get_nested_string(
+            change,
+            &["data", "data", handle, "guid", "id", "creation_num"],
+        );
+        (number == creation_number).then(|| coin_type.to_owned())
+    })
+}
+
+fn check_strict_present(strict: bool, version: u64, event_type: &str, field: &str) -> Result<()> {
+    if strict {
+        return Err(anyhow!(
+            "transaction {version}: `{event_type}` event has no {field}"
+        ));
+    }
+    Ok(())
+}
+
+fn check_strict_amount(strict: bool, version: u64, field: &str, amount: &str) -> Result<()> {
+    if strict && BigInt::from_str(amount).is_err() {
+        return Err(anyhow!(
+            "transaction {version}: `{field}` value {amount:?} is not an integer"
+        ));
+    }
+    Ok(())
 }
 
 fn extract_transfer_store_info_from_tx(tx: &Value) -> HashMap<String, TransferStoreMetadata> {
@@ -615,7 +1637,19 @@ fn extract_transfer_store_info_from_tx(tx: &Value) -> HashMap<String, TransferSt
     info
 }
 
-fn find_sender_apt_store(tx: &Value, sender: &str) -> String {
+/// The sponsor of a fee-payer transaction, who pays gas instead of the sender.
+fn fee_payer_address(tx: &Value) -> Option<String> {
+    let signature = tx.get("signature")?;
+    if signature.get("type").and_then(Value::as_str) != Some("fee_payer_signature") {
+        return None;
+    }
+    signature
+        .get("fee_payer_address")
+        .and_then(Value::as_str)
+        .map(str::to_owned)
+}
+
+fn find_owner_apt_store(tx: &Value, owner: &str) -> String {
     let Some(changes) = tx.get("changes").and_then(Value::as_array) else {
         return String::new();
     };
@@ -661,7 +1695,7 @@ fn find_sender_apt_store(tx: &Value, sender: &str) -> String {
             .unwrap_or_default()
             .to_owned();
         let asset = get_nested_string(change, &["data", "data", "metadata", "inner"]);
-        if owners.get(&address).map(String::as_str) == Some(sender) && asset == "0xa" {
+        if owners.get(&address).map(String::as_str) == Some(owner) && is_apt_fa_address(&asset) {
             return address;
         }
     }
@@ -686,13 +1720,13 @@ fn query_transfer_store_info(
 
     let object_type = urlencoding::encode(OBJECT_CORE_TYPE);
     let object_path = format!("/accounts/{store}/resource/{object_type}{query}");
-    if let Ok(value) = client.get_json(&object_path) {
+    if let Ok(value) = client.get_json_cached(&object_path) {
         metadata.owner = get_nested_string(&value, &["data", "owner"]);
     }
 
     let store_type = urlencoding::encode(FUNGIBLE_STORE_TYPE);
     let store_path = format!("/accounts/{store}/resource/{store_type}{query}");
-    if let Ok(value) = client.get_json(&store_path) {
+    if let Ok(value) = client.get_json_cached(&store_path) {
         metadata.asset = get_nested_string(&value, &["data", "metadata", "inner"]);
     }
 
@@ -729,6 +1763,7 @@ fn aggregate_events(events: &[BalanceChange]) -> Vec<AggregatedBalanceChange> {
                 .unwrap_or_else(|| "0".to_owned()),
             account,
             asset,
+            symbol: None,
         })
         .collect()
 }
@@ -738,6 +1773,162 @@ fn parse_bigint(value: &Value) -> BigInt {
     BigInt::from_str(&string_value).unwrap_or_else(|_| BigInt::from(0))
 }
 
-fn first_non_empty_string(values: &[String]) -> Option<String> {
-    values.iter().find(|value| !value.is_empty()).cloned()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strict_checks_reject_non_integer_or_missing_amounts() {
+        assert!(check_strict_amount(true, 7, "gas_used", "12").is_ok());
+        assert!(check_strict_amount(true, 7, "gas_used", "-3").is_ok());
+        assert!(check_strict_amount(false, 7, "gas_used", "1.5").is_ok());
+        assert_eq!(
+            check_strict_amount(true, 7, "gas_used", "1.5")
+                .unwrap_err()
+                .to_string(),
+            "transaction 7: `gas_used` value \"1.5\" is not an integer"
+        );
+        assert!(check_strict_amount(true, 7, "gas_used", "").is_err());
+
+        let event = json!({"type": "0x1::coin::CoinDeposit", "data": {"account": "0x1", "coin_type": "0x1::aptos_coin::AptosCoin"}});
+        let tx = json!({});
+        assert!(
+            coin_balance_change(&tx, &event, "0x1::coin::CoinDeposit", false, 7)
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            coin_balance_change(&tx, &event, "0x1::coin::CoinDeposit", true, 7)
+                .unwrap_err()
+                .to_string(),
+            "transaction 7: `0x1::coin::CoinDeposit` event has no amount"
+        );
+    }
+
+    fn change(event_type: &str, asset: &str, amount: &str) -> BalanceChange {
+        BalanceChange {
+            event_type: event_type.to_owned(),
+            account: "0xb".to_owned(),
+            fungible_store: String::new(),
+            asset: asset.to_owned(),
+            amount: amount.to_owned(),
+            symbol: None,
+            fee_payer: None,
+        }
+    }
+
+    #[test]
+    fn drops_coin_events_mirrored_by_paired_fa_events() {
+        const COIN: &str = "0x1::aptos_coin::AptosCoin";
+        let fa_events = [change("deposit", "0xa", "100")];
+        let coin_events = || {
+            vec![
+                change("deposit", COIN, "100"),
+                change("withdraw", COIN, "100"),
+            ]
+        };
+
+        // Paired with the FA asset: the matching deposit is a duplicate.
+        let mut lookups = 0;
+        let unique = drop_paired_coin_events(&fa_events, coin_events(), |_| {
+            lookups += 1;
+            Ok(Some(
+                "0x000000000000000000000000000000000000000000000000000000000000000a".to_owned(),
+            ))
+        })
+        .unwrap();
+        assert_eq!(lookups, 1);
+        assert_eq!(unique.len(), 1);
+        assert_eq!(unique[0].event_type, "withdraw");
+
+        // Unpaired, or paired with another asset: both coin events stay.
+        let unique = drop_paired_coin_events(&fa_events, coin_events(), |_| Ok(None)).unwrap();
+        assert_eq!(unique.len(), 2);
+        let unique =
+            drop_paired_coin_events(&fa_events, coin_events(), |_| Ok(Some("0xbeef".to_owned())))
+                .unwrap();
+        assert_eq!(unique.len(), 2);
+
+        // A failed lookup is reported instead of double counting.
+        let err = drop_paired_coin_events(&fa_events, coin_events(), |_| {
+            Err(anyhow!("node unavailable"))
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "node unavailable");
+    }
+
+    #[test]
+    fn charges_gas_to_the_fee_payer_of_sponsored_transactions() {
+        let store = |address: &str, owner: &str| {
+            [
+                json!({
+                    "type": "write_resource",
+                    "address": address,
+                    "data": {"type": OBJECT_CORE_TYPE, "data": {"owner": owner}}
+                }),
+                json!({
+                    "type": "write_resource",
+                    "address": address,
+                    "data": {
+                        "type": FUNGIBLE_STORE_TYPE,
+                        "data": {"metadata": {"inner": "0xa"}}
+                    }
+                }),
+            ]
+        };
+        let changes: Vec<Value> = store("0xs1", "0x5e4d")
+            .into_iter()
+            .chain(store("0xs2", "0xfee"))
+            .collect();
+        let tx = json!({
+            "version": "7",
+            "sender": "0x5e4d",
+            "gas_used": "10",
+            "gas_unit_price": "100",
+            "signature": {
+                "type": "fee_payer_signature",
+                "sender": {},
+                "fee_payer_address": "0xfee"
+            },
+            "changes": changes,
+            "events": []
+        });
+        // No events, so the client is never called.
+        let client = AptosClient::new("http://127.0.0.1:9/v1").unwrap();
+
+        let events =
+            build_balance_change_events(&tx, &mut HashMap::new(), &client, 7, true, true).unwrap();
+        assert_eq!(events.len(), 1);
+        let gas = &events[0];
+        assert_eq!(gas.event_type, "gas_fee");
+        assert_eq!(gas.account, "0xfee");
+        assert_eq!(gas.fungible_store, "0xs2");
+        assert_eq!(gas.amount, "1000");
+        assert_eq!(gas.fee_payer.as_deref(), Some("0xfee"));
+    }
+
+    #[test]
+    fn decodes_bytes_options_and_objects() {
+        assert_eq!(
+            decode_argument("vector<u8>", &json!("0x68656c6c6f")),
+            Some(json!("hello"))
+        );
+        assert_eq!(decode_argument("vector<u8>", &json!("0x00ff")), None);
+        assert_eq!(
+            decode_argument("0x1::option::Option<u64>", &json!({"vec": ["5"]})),
+            Some(json!("5"))
+        );
+        assert_eq!(
+            decode_argument("0x1::option::Option<u64>", &json!({"vec": []})),
+            Some(Value::Null)
+        );
+        assert_eq!(
+            decode_argument(
+                "0x1::object::Object<0x1::fungible_asset::Metadata>",
+                &json!({"inner": "0xa"})
+            ),
+            Some(json!("0xa"))
+        );
+        assert_eq!(decode_argument("u64", &json!("5")), None);
+    }
 }
@@ -1,13 +1,18 @@
-use anyhow::{Context, Result};
-use aptly_aptos::AptosClient;
+use anyhow::{anyhow, Context, Result};
+use aptly_aptos::{substitute_type_parameters, validate_type_string, AptosClient};
 use clap::Args;
 use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use crate::commands::common::with_optional_ledger_version;
+use crate::commands::common::{fetch_function_abi, unwrap_options, with_optional_ledger_version};
+
+/// `--args` token replaced by the `--signer` address.
+const SIGNER_PLACEHOLDER: &str = "__signer__";
 
 #[derive(Args)]
 #[command(
-    after_help = "Examples:\n  aptly view 0x1::coin::balance --type-args 0x1::aptos_coin::AptosCoin --args '\"0x1\"'\n  aptly view 0x1::stake::get_current_epoch --ledger-version 4300000000"
+    after_help = "Examples:\n  aptly view 0x1::coin::balance --type-args 0x1::aptos_coin::AptosCoin --args '\"0x1\"'\n  aptly view 0x1::stake::get_current_epoch --ledger-version 4300000000\n  aptly view 0x1::coin::balance --type-args 0x1::aptos_coin::AptosCoin --signer 0x1\n  aptly view 0x1::coin::supply --type-args 0x1::aptos_coin::AptosCoin --unwrap-options\n  aptly view 0x1::stake::get_lockup_secs --args '\"0x1\"' --decode-abi\n  aptly view 0x1::primary_fungible_store::balance --type-args 0x1::object::ObjectCore --args __signer__ --args '\"0xa\"' --signer 0x1\n  aptly view 0x1::coin::balance --type-args-file type_args.json --args-file args.json"
 )]
 pub(crate) struct ViewCommand {
     /// Fully-qualified Move function, e.g. `0x1::coin::balance`.
@@ -16,29 +21,190 @@ pub(crate) struct ViewCommand {
     /// Repeatable type arguments.
     #[arg(long = "type-args")]
     pub(crate) type_args: Vec<String>,
+    /// Read type arguments from a JSON array of strings in this file.
+    #[arg(long, value_name = "PATH", conflicts_with = "type_args")]
+    pub(crate) type_args_file: Option<PathBuf>,
     /// Repeatable JSON arguments.
     #[arg(long = "args")]
     pub(crate) args: Vec<String>,
+    /// Read arguments from a JSON array in this file, for large vectors.
+    #[arg(long, value_name = "PATH", conflicts_with = "args")]
+    pub(crate) args_file: Option<PathBuf>,
     /// Optional ledger version for historical view execution.
     #[arg(long)]
     pub(crate) ledger_version: Option<u64>,
+    /// Address substituted for a `__signer__` token in `--args`, or appended
+    /// as the last argument when no token is present.
+    #[arg(long, value_name = "ADDRESS")]
+    pub(crate) signer: Option<String>,
+    /// Render `Option` values (`{"vec": [x]}` / `{"vec": []}`) as `x` / `null`.
+    #[arg(long, default_value_t = false)]
+    pub(crate) unwrap_options: bool,
+    /// Label each return value with its Move type from the module ABI, as
+    /// `{"0": {"type": .., "value": ..}, ..}`. Generic types are resolved
+    /// with `--type-args`.
+    #[arg(long, default_value_t = false)]
+    pub(crate) decode_abi: bool,
 }
 
 pub(crate) fn run_view(client: &AptosClient, command: ViewCommand) -> Result<()> {
-    let mut parsed_args = Vec::with_capacity(command.args.len());
-    for argument in &command.args {
-        let parsed: Value = serde_json::from_str(argument)
-            .with_context(|| format!("failed to parse argument {argument:?} as JSON"))?;
-        parsed_args.push(parsed);
+    let type_args = match &command.type_args_file {
+        Some(path) => read_json_array_file(path)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| match value {
+                Value::String(type_arg) => Ok(type_arg),
+                other => Err(anyhow!(
+                    "{}: element {index} is {other}, expected a type string",
+                    path.display()
+                )),
+            })
+            .collect::<Result<Vec<_>>>()?,
+        None => command.type_args,
+    };
+    for type_arg in &type_args {
+        validate_type_string(type_arg)?;
+    }
+
+    let arguments = match &command.args_file {
+        Some(path) => read_json_array_file(path)?,
+        None => command
+            .args
+            .iter()
+            .map(|argument| {
+                if is_signer_placeholder(argument) {
+                    return Ok(Value::String(SIGNER_PLACEHOLDER.to_owned()));
+                }
+                serde_json::from_str(argument)
+                    .with_context(|| format!("failed to parse argument {argument:?} as JSON"))
+            })
+            .collect::<Result<Vec<_>>>()?,
+    };
+    let mut parsed_args = Vec::with_capacity(arguments.len() + 1);
+    let mut signer_used = false;
+    for argument in arguments {
+        if argument.as_str() == Some(SIGNER_PLACEHOLDER) {
+            let signer = command.signer.as_ref().ok_or_else(|| {
+                anyhow!("argument `{SIGNER_PLACEHOLDER}` requires --signer <ADDRESS>")
+            })?;
+            parsed_args.push(Value::String(signer.clone()));
+            signer_used = true;
+            continue;
+        }
+        parsed_args.push(argument);
+    }
+    if let (Some(signer), false) = (&command.signer, signer_used) {
+        parsed_args.push(Value::String(signer.clone()));
     }
 
     let body = json!({
         "function": command.function,
-        "type_arguments": command.type_args,
+        "type_arguments": type_args,
         "arguments": parsed_args
     });
 
-    let path = with_optional_ledger_version("/view", command.ledger_version);
-    let value = client.post_json(&path, &body)?;
+    let path = with_optional_ledger_version(client, "/view", command.ledger_version)?;
+    let mut value = client.post_json_cached(&path, &body)?;
+    if command.unwrap_options {
+        unwrap_options(&mut value);
+    }
+    if command.decode_abi {
+        match fetch_return_types(client, &command.function, command.ledger_version) {
+            Ok(return_types) => value = label_returns(value, &return_types, &type_args),
+            Err(err) => eprintln!("warning: printing raw values; ABI lookup failed: {err:#}"),
+        }
+    }
     crate::print_pretty_json(&value)
 }
+
+/// The `return` types of `function` from its module's ABI.
+fn fetch_return_types(
+    client: &AptosClient,
+    function: &str,
+    ledger_version: Option<u64>,
+) -> Result<Vec<String>> {
+    let abi_function = fetch_function_abi(client, function, ledger_version)?;
+    Ok(abi_function
+        .get("return")
+        .and_then(Value::as_array)
+        .map(|types| {
+            types
+                .iter()
+                .filter_map(|ty| ty.as_str().map(str::to_owned))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Pairs each positional return value with its ABI type, keyed by index,
+/// with `T0`, `T1`, .. replaced by the matching type argument.
+fn label_returns(value: Value, return_types: &[String], type_args: &[String]) -> Value {
+    let Value::Array(values) = value else {
+        return value;
+    };
+    if values.len() != return_types.len() {
+        eprintln!(
+            "warning: ABI declares {} return value(s) but the node returned {}; printing raw values",
+            return_types.len(),
+            values.len()
+        );
+        return Value::Array(values);
+    }
+
+    let labeled = values
+        .into_iter()
+        .zip(return_types)
+        .enumerate()
+        .map(|(index, (value, ty))| {
+            let ty = substitute_type_parameters(ty, type_args);
+            (index.to_string(), json!({ "type": ty, "value": value }))
+        })
+        .collect();
+    Value::Object(labeled)
+}
+
+fn read_json_array_file(path: &Path) -> Result<Vec<Value>> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let value: Value = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse {} as JSON", path.display()))?;
+    match value {
+        Value::Array(items) => Ok(items),
+        _ => Err(anyhow!(
+            "{} must contain a JSON array, e.g. [\"0x1\", \"100\"]",
+            path.display()
+        )),
+    }
+}
+
+fn is_signer_placeholder(argument: &str) -> bool {
+    let argument = argument.trim();
+    argument == SIGNER_PLACEHOLDER || argument.trim_matches('"') == SIGNER_PLACEHOLDER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_returns_by_index_with_type_arguments_substituted() {
+        let labeled = label_returns(
+            json!(["100", true]),
+            &["0x1::coin::Coin<T0>".to_owned(), "bool".to_owned()],
+            &["0x1::aptos_coin::AptosCoin".to_owned()],
+        );
+        assert_eq!(
+            labeled,
+            json!({
+                "0": {"type": "0x1::coin::Coin<0x1::aptos_coin::AptosCoin>", "value": "100"},
+                "1": {"type": "bool", "value": true}
+            })
+        );
+    }
+
+    #[test]
+    fn keeps_raw_values_when_return_count_differs_from_abi() {
+        let raw = json!(["100", true]);
+        assert_eq!(label_returns(raw.clone(), &["u64".to_owned()], &[]), raw);
+    }
+}
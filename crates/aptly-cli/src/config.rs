@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Defaults read from `~/.config/aptly/config.toml` (or `--config`). CLI
+/// flags and environment variables override every field.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    pub(crate) rpc_url: Option<String>,
+    pub(crate) network: Option<String>,
+    pub(crate) api_key: Option<String>,
+    pub(crate) max_retries: Option<u32>,
+}
+
+impl Config {
+    /// Loads `explicit`, or the default path when it exists. A missing
+    /// default file yields an empty config; a missing explicit one is an error.
+    pub(crate) fn load(explicit: Option<&Path>) -> Result<Self> {
+        let (path, required) = match explicit {
+            Some(path) => (path.to_owned(), true),
+            None => match default_config_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(err) if !required && err.kind() == io::ErrorKind::NotFound => {
+                return Ok(Self::default())
+            }
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("failed to read config file {}", path.display()))
+            }
+        };
+        toml::from_str(&text).with_context(|| format!("invalid config file {}", path.display()))
+    }
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("config.toml"))
+}
+
+/// `$XDG_CONFIG_HOME/aptly`, else `~/.config/aptly`.
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("aptly"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_config() {
+        let config: Config = toml::from_str(
+            "# local node\nrpc_url = \"http://localhost:8080/v1\" # comment\napi_key = 'k#1'\nnetwork = \"\\u0074estnet\"\nmax_retries = 5\n",
+        )
+        .unwrap();
+        assert_eq!(config.rpc_url.as_deref(), Some("http://localhost:8080/v1"));
+        assert_eq!(config.api_key.as_deref(), Some("k#1"));
+        assert_eq!(config.network.as_deref(), Some("testnet"));
+        assert_eq!(config.max_retries, Some(5));
+    }
+
+    #[test]
+    fn rejects_unknown_or_malformed_config() {
+        assert!(toml::from_str::<Config>("rpc = \"x\"\n").is_err());
+        assert!(toml::from_str::<Config>("rpc_url = http://x\n").is_err());
+        assert!(toml::from_str::<Config>("[node]\nrpc_url = \"x\"\n").is_err());
+    }
+}
@@ -1,10 +1,21 @@
-use anyhow::Result;
-use aptly_aptos::AptosClient;
-use clap::{Parser, Subcommand};
+use anyhow::{anyhow, Result};
+use aptly_aptos::{AptosClient, HttpConfig, DEFAULT_CONCURRENCY};
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
 use serde::Serialize;
 use serde_json::Value;
+use std::env;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use config::Config;
+use output::{print_output, ColorChoice, OutputFormat};
 
 mod commands;
+mod config;
+mod output;
+mod pager;
 mod plugin_tools;
 
 use commands::account::{run_account, AccountCommand};
@@ -19,14 +30,95 @@ use commands::tx::{run_tx, TxCommand};
 use commands::view::{run_view, ViewCommand};
 
 const DEFAULT_RPC_URL: &str = "https://rpc.sentio.xyz/aptos/v1";
+/// `--network` names and their Aptos Labs fullnode endpoints.
+const NETWORKS: &[(&str, &str)] = &[
+    ("mainnet", "https://api.mainnet.aptoslabs.com/v1"),
+    ("testnet", "https://api.testnet.aptoslabs.com/v1"),
+    ("devnet", "https://api.devnet.aptoslabs.com/v1"),
+];
+/// Node request retries when neither `--max-retries` nor the config sets one.
+const DEFAULT_MAX_RETRIES: u32 = 3;
+/// Upper bound on memoized view/resource reads kept per process.
+const RESPONSE_CACHE_CAPACITY: usize = 1024;
 
 #[derive(Parser)]
 #[command(name = "aptly")]
 #[command(about = "Aptos CLI utilities in Rust")]
 struct Cli {
-    /// Aptos node REST API endpoint.
-    #[arg(long, global = true, default_value = DEFAULT_RPC_URL)]
-    rpc_url: String,
+    /// Aptos node REST API endpoint. Takes precedence over `--network`;
+    /// defaults to the Sentio mainnet RPC.
+    #[arg(long, global = true, value_name = "URL")]
+    rpc_url: Option<String>,
+
+    /// Use the Aptos Labs fullnode for `mainnet`, `testnet`, or `devnet`.
+    /// Defaults to the `APTLY_NETWORK` environment variable.
+    #[arg(long, global = true, value_name = "NAME")]
+    network: Option<String>,
+
+    /// Accept invalid TLS certificates, e.g. a self-signed local node.
+    /// UNSAFE: disables certificate verification for all requests; only
+    /// honored when passed explicitly.
+    #[arg(
+        long,
+        visible_alias = "danger-accept-invalid-certs",
+        global = true,
+        default_value_t = false
+    )]
+    insecure: bool,
+
+    /// Send all HTTP requests through this proxy. Defaults to the
+    /// `HTTPS_PROXY`/`HTTP_PROXY`/`NO_PROXY` environment variables.
+    #[arg(long, global = true, value_name = "URL")]
+    proxy: Option<String>,
+
+    /// API key sent as `Authorization: Bearer <key>` on node requests.
+    /// Defaults to the `APTLY_API_KEY` environment variable.
+    #[arg(long, global = true, value_name = "KEY")]
+    api_key: Option<String>,
+
+    /// Skip checking `--ledger-version` against the node's pruning horizon.
+    #[arg(long, global = true, default_value_t = false)]
+    no_prune_check: bool,
+
+    /// Retries for rate-limited (429) and gateway (502/503/504) node
+    /// responses, with exponential backoff from 200ms [default: 3].
+    #[arg(long, global = true, value_name = "N")]
+    max_retries: Option<u32>,
+
+    /// Seconds to wait for each node API response before failing
+    /// [default: 30]. Also bounds label and trace fetches when set.
+    #[arg(long, global = true, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    timeout_secs: Option<u64>,
+
+    /// Requests kept in flight by commands that make many node calls
+    /// (`account sends`, `account balance --all`, `tx batch`).
+    #[arg(long, global = true, value_name = "N", default_value_t = DEFAULT_CONCURRENCY as u64, value_parser = clap::value_parser!(u64).range(1..))]
+    concurrency: u64,
+
+    /// Config file with default `rpc_url`, `network`, `api_key`, and
+    /// `max_retries` [default: ~/.config/aptly/config.toml].
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Output format. CSV renders arrays of objects as rows and anything
+    /// else as a single `value` column of compact JSON.
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Json)]
+    format: OutputFormat,
+
+    /// Syntax-highlight JSON output. `auto` colors only when stdout is a
+    /// terminal and `NO_COLOR` is unset.
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Write command output to this file instead of stdout, creating parent
+    /// directories as needed.
+    #[arg(long, global = true, value_name = "PATH", conflicts_with = "pager")]
+    output: Option<PathBuf>,
+
+    /// Page output through `$APTLY_PAGER`, `$PAGER`, or `less -R` when
+    /// stdout is a terminal.
+    #[arg(long, global = true, default_value_t = false)]
+    pager: bool,
 
     #[command(subcommand)]
     command: Command,
@@ -86,28 +178,79 @@ enum Command {
     Tx(TxCommand),
     #[command(about = "Print build version information")]
     Version,
+    #[command(
+        about = "Print a shell completion script",
+        after_help = "Examples:\n  aptly completions bash > ~/.local/share/bash-completion/completions/aptly\n  aptly completions zsh > ~/.zfunc/_aptly\n  aptly completions fish > ~/.config/fish/completions/aptly.fish"
+    )]
+    Completions {
+        /// Shell to generate the script for.
+        #[arg(value_enum)]
+        shell: Shell,
+    },
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    let rpc_url = cli.rpc_url.clone();
+    output::set_format(cli.format);
+    output::set_color(cli.color, cli.output.is_some());
+    match &cli.output {
+        Some(path) => pager::redirect_to_file(path)?,
+        None => pager::start(cli.pager),
+    }
+    let result = run(cli).and_then(|()| pager::commit_output());
+    pager::finish();
+    result
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let http_config = HttpConfig {
+        accept_invalid_certs: cli.insecure,
+        timeout: cli.timeout_secs.map(Duration::from_secs),
+        proxy: cli.proxy.clone(),
+    };
+    if http_config.accept_invalid_certs {
+        eprintln!("warning: TLS certificate verification is disabled (--insecure)");
+    }
 
     match cli.command {
-        Command::Version => print_version(),
+        Command::Version => print_version()?,
         Command::Plugin(command) => run_plugin(command)?,
-        Command::Decompile(command) => run_decompile(&rpc_url, command)?,
+        Command::Completions { shell } => {
+            // `generate` panics on write errors, so render first and write with `?`.
+            let mut script = Vec::new();
+            clap_complete::generate(shell, &mut Cli::command(), "aptly", &mut script);
+            pager::stdout().write_all(&script)?;
+        }
         command => {
-            let client = AptosClient::new(&rpc_url)?;
+            let config = Config::load(cli.config.as_deref())?;
+            let rpc_url = resolve_rpc_url(cli.rpc_url, cli.network, &config)?;
+            let api_key = cli
+                .api_key
+                .or_else(|| env::var("APTLY_API_KEY").ok())
+                .or(config.api_key);
+            let max_retries = cli
+                .max_retries
+                .or(config.max_retries)
+                .unwrap_or(DEFAULT_MAX_RETRIES);
+            let client = AptosClient::with_config(&rpc_url, &http_config)?
+                .with_response_cache(RESPONSE_CACHE_CAPACITY)
+                .with_prune_check(!cli.no_prune_check)
+                .with_retries(max_retries)
+                .with_api_key(api_key)
+                .with_concurrency(cli.concurrency as usize);
             match command {
-                Command::Node(command) => run_node(&client, command)?,
+                Command::Node(command) => run_node(&client, &rpc_url, command)?,
                 Command::Account(command) => run_account(&client, command)?,
-                Command::Address(command) => run_address(command)?,
+                Command::Address(command) => run_address(&client, command)?,
+                Command::Decompile(command) => run_decompile(&client, command)?,
                 Command::Block(command) => run_block(&client, command)?,
                 Command::Events(command) => run_events(&client, command)?,
                 Command::Table(command) => run_table(&client, command)?,
                 Command::View(command) => run_view(&client, command)?,
                 Command::Tx(command) => run_tx(&client, &rpc_url, command)?,
-                Command::Plugin(_) | Command::Decompile(_) | Command::Version => unreachable!(),
+                Command::Plugin(_) | Command::Version | Command::Completions { .. } => {
+                    unreachable!()
+                }
             }
         }
     }
@@ -115,23 +258,108 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn print_version() {
+/// Flags, then `APTLY_NETWORK`, then the config file, then the default RPC.
+/// An explicit RPC URL beats a network name at the same level.
+fn resolve_rpc_url(
+    rpc_url: Option<String>,
+    network: Option<String>,
+    config: &Config,
+) -> Result<String> {
+    if let Some(rpc_url) = rpc_url {
+        return Ok(rpc_url);
+    }
+    if let Some(network) = network.or_else(|| env::var("APTLY_NETWORK").ok()) {
+        return network_rpc_url(&network);
+    }
+    if let Some(rpc_url) = &config.rpc_url {
+        return Ok(rpc_url.clone());
+    }
+    match &config.network {
+        Some(network) => network_rpc_url(network),
+        None => Ok(DEFAULT_RPC_URL.to_owned()),
+    }
+}
+
+fn network_rpc_url(network: &str) -> Result<String> {
+    NETWORKS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(network.trim()))
+        .map(|(_, url)| (*url).to_owned())
+        .ok_or_else(|| {
+            let names: Vec<&str> = NETWORKS.iter().map(|(name, _)| *name).collect();
+            anyhow!(
+                "unknown network `{network}`; supported networks: {}",
+                names.join(", ")
+            )
+        })
+}
+
+fn print_version() -> Result<()> {
     let version = env!("APTLY_VERSION");
     let commit_sha = env!("APTLY_GIT_SHA");
     let build_date = env!("APTLY_BUILD_DATE");
 
-    println!("aptly {version}");
-    println!("commit: {commit_sha}");
-    println!("built: {build_date}");
+    let mut out = pager::stdout();
+    writeln!(out, "aptly {version}")?;
+    writeln!(out, "commit: {commit_sha}")?;
+    writeln!(out, "built: {build_date}")?;
+    Ok(())
 }
 
 pub(crate) fn print_pretty_json(value: &Value) -> Result<()> {
-    let rendered = serde_json::to_string_pretty(value)?;
-    println!("{rendered}");
-    Ok(())
+    print_output(value, output::format())
 }
 
 pub(crate) fn print_serialized<T: Serialize>(value: &T) -> Result<()> {
     let json_value = serde_json::to_value(value)?;
     print_pretty_json(&json_value)
 }
+
+/// Writes a pretty-printed JSON array to stdout one element at a time, so
+/// paginated output never has to be held in memory. The rendered bytes match
+/// `print_pretty_json` on the equivalent array. Other `--format`s need the
+/// whole array, so elements are collected and rendered on `finish`.
+pub(crate) struct JsonArrayWriter {
+    out: io::BufWriter<pager::PagedStdout>,
+    empty: bool,
+    buffered: Option<Vec<Value>>,
+}
+
+impl JsonArrayWriter {
+    pub(crate) fn stdout() -> Self {
+        let buffered = (output::format() != OutputFormat::Json).then(Vec::new);
+        Self {
+            out: io::BufWriter::new(pager::stdout()),
+            empty: true,
+            buffered,
+        }
+    }
+
+    pub(crate) fn push(&mut self, value: &Value) -> Result<()> {
+        if let Some(buffered) = self.buffered.as_mut() {
+            buffered.push(value.clone());
+            return Ok(());
+        }
+        let separator = if self.empty { "[\n" } else { ",\n" };
+        self.out.write_all(separator.as_bytes())?;
+        let rendered = output::render_json(value)?;
+        for (index, line) in rendered.lines().enumerate() {
+            if index > 0 {
+                self.out.write_all(b"\n")?;
+            }
+            write!(self.out, "  {line}")?;
+        }
+        self.empty = false;
+        Ok(())
+    }
+
+    pub(crate) fn finish(mut self) -> Result<()> {
+        if let Some(buffered) = self.buffered.take() {
+            return print_output(&Value::Array(buffered), output::format());
+        }
+        let closing = if self.empty { "[]\n" } else { "\n]\n" };
+        self.out.write_all(closing.as_bytes())?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+use std::env;
+use std::io::{self, IsTerminal, Write};
+use std::sync::OnceLock;
+
+use crate::pager;
+
+static FORMAT: OnceLock<OutputFormat> = OnceLock::new();
+static COLOR: OnceLock<bool> = OnceLock::new();
+
+const KEY_COLOR: &str = "\x1b[1;34m";
+const STRING_COLOR: &str = "\x1b[32m";
+const NUMBER_COLOR: &str = "\x1b[36m";
+const BOOL_COLOR: &str = "\x1b[33m";
+const NULL_COLOR: &str = "\x1b[90m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum OutputFormat {
+    #[default]
+    Json,
+    Yaml,
+    Csv,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub(crate) enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Decides once whether JSON output is colored. `auto` colors only a
+/// terminal stdout, never an `--output` file, and honors `NO_COLOR`.
+pub(crate) fn set_color(choice: ColorChoice, to_file: bool) {
+    let enabled = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            !to_file
+                && io::stdout().is_terminal()
+                && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+        }
+    };
+    let _ = COLOR.set(enabled);
+}
+
+/// Sets the format used by [`print_output`] for the rest of the run.
+pub(crate) fn set_format(format: OutputFormat) {
+    let _ = FORMAT.set(format);
+}
+
+pub(crate) fn format() -> OutputFormat {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// Writes `value` to stdout (or the pager) in `format`.
+pub(crate) fn print_output(value: &Value, format: OutputFormat) -> Result<()> {
+    let rendered = match format {
+        OutputFormat::Json => render_json(value)?,
+        OutputFormat::Yaml => serde_yaml::to_string(value)?,
+        OutputFormat::Csv => render_csv(value),
+    };
+    writeln!(pager::stdout(), "{}", rendered.trim_end_matches('\n'))?;
+    Ok(())
+}
+
+/// Pretty JSON, syntax-highlighted when [`set_color`] enabled it. Apart
+/// from the escape codes it matches `serde_json::to_string_pretty`.
+pub(crate) fn render_json(value: &Value) -> Result<String> {
+    if !COLOR.get().copied().unwrap_or_default() {
+        return Ok(serde_json::to_string_pretty(value)?);
+    }
+    let mut out = String::new();
+    write_colored_json(&mut out, value, 0)?;
+    Ok(out)
+}
+
+fn write_colored_json(out: &mut String, value: &Value, indent: usize) -> Result<()> {
+    match value {
+        Value::Null => paint(out, NULL_COLOR, "null"),
+        Value::Bool(flag) => paint(out, BOOL_COLOR, &flag.to_string()),
+        Value::Number(number) => paint(out, NUMBER_COLOR, &number.to_string()),
+        Value::String(text) => paint(out, STRING_COLOR, &serde_json::to_string(text)?),
+        Value::Array(items) if items.is_empty() => out.push_str("[]"),
+        Value::Object(map) if map.is_empty() => out.push_str("{}"),
+        Value::Array(items) => {
+            out.push_str("[\n");
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(",\n");
+                }
+                out.push_str(&" ".repeat(indent + 2));
+                write_colored_json(out, item, indent + 2)?;
+            }
+            out.push('\n');
+            out.push_str(&" ".repeat(indent));
+            out.push(']');
+        }
+        Value::Object(map) => {
+            out.push_str("{\n");
+            for (index, (key, item)) in map.iter().enumerate() {
+                if index > 0 {
+                    out.push_str(",\n");
+                }
+                out.push_str(&" ".repeat(indent + 2));
+                paint(out, KEY_COLOR, &serde_json::to_string(key)?);
+                out.push_str(": ");
+                write_colored_json(out, item, indent + 2)?;
+            }
+            out.push('\n');
+            out.push_str(&" ".repeat(indent));
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+fn paint(out: &mut String, color: &str, text: &str) {
+    out.push_str(color);
+    out.push_str(text);
+    out.push_str(RESET);
+}
+
+/// One header row plus a row per element for arrays of objects; anything
+/// else becomes a single `value` column of compact JSON.
+fn render_csv(value: &Value) -> String {
+    let rows: Option<Vec<&Map<String, Value>>> = match value {
+        Value::Array(items) if !items.is_empty() => items.iter().map(Value::as_object).collect(),
+        _ => None,
+    };
+    let Some(rows) = rows else {
+        let items = match value {
+            Value::Array(items) => items.iter().collect(),
+            other => vec![other],
+        };
+        let mut out = String::from("value\n");
+        for item in items {
+            out.push_str(&csv_field(&item.to_string()));
+            out.push('\n');
+        }
+        return out;
+    };
+
+    let mut columns: Vec<&str> = Vec::new();
+    for row in &rows {
+        for key in row.keys() {
+            if !columns.contains(&key.as_str()) {
+                columns.push(key);
+            }
+        }
+    }
+
+    let mut out = csv_row(columns.iter().map(|column| column.to_string()));
+    for row in rows {
+        out.push_str(&csv_row(columns.iter().map(
+            |column| match row.get(*column) {
+                None | Some(Value::Null) => String::new(),
+                Some(Value::String(value)) => value.clone(),
+                Some(other) => other.to_string(),
+            },
+        )));
+    }
+    out
+}
+
+fn csv_row(fields: impl Iterator<Item = String>) -> String {
+    let mut row = fields
+        .map(|field| csv_field(&field))
+        .collect::<Vec<_>>()
+        .join(",");
+    row.push('\n');
+    row
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn colored_json_matches_pretty_json_without_escapes() {
+        let value = json!({
+            "text": "a \"quoted\"\nline",
+            "n": 1.5,
+            "flags": [true, null, {}, []],
+            "nested": {"k": -2},
+        });
+        let mut colored = String::new();
+        write_colored_json(&mut colored, &value, 0).unwrap();
+        assert!(colored.contains("\x1b[1;34m\"text\"\x1b[0m: \x1b[32m"));
+
+        let mut plain = String::new();
+        let mut rest = colored.as_str();
+        while let Some(start) = rest.find('\x1b') {
+            plain.push_str(&rest[..start]);
+            rest = &rest[start + rest[start..].find('m').unwrap() + 1..];
+        }
+        plain.push_str(rest);
+        assert_eq!(plain, serde_json::to_string_pretty(&value).unwrap());
+    }
+
+    #[test]
+    fn yaml_keeps_address_and_amount_strings_as_strings() {
+        let value = json!([{"address": "0x1", "amount": "100"}]);
+        let yaml = serde_yaml::to_string(&value).unwrap();
+        assert_eq!(yaml, "- address: '0x1'\n  amount: '100'\n");
+        assert_eq!(serde_yaml::from_str::<Value>(&yaml).unwrap(), value);
+    }
+
+    #[test]
+    fn renders_csv_rows_or_value_column() {
+        let rows = json!([
+            {"account": "0x1", "delta": "-5"},
+            {"account": "0x2", "delta": "5", "note": "a,b"},
+        ]);
+        assert_eq!(
+            render_csv(&rows),
+            "account,delta,note\n0x1,-5,\n0x2,5,\"a,b\"\n"
+        );
+        assert_eq!(
+            render_csv(&json!({"chain_id": 1})),
+            "value\n\"{\"\"chain_id\"\":1}\"\n"
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{Mutex, MutexGuard};
+use tempfile::NamedTempFile;
+
+const DEFAULT_PAGER: &str = "less -R";
+
+static PAGER: Mutex<Option<Pager>> = Mutex::new(None);
+static OUTPUT_FILE: Mutex<Option<OutputFile>> = Mutex::new(None);
+
+/// `--output` is written to a temp file beside `path` and only renamed over
+/// it by [`commit_output`], so a failed command leaves an existing file as-is.
+struct OutputFile {
+    temp: NamedTempFile,
+    path: PathBuf,
+}
+
+struct Pager {
+    child: Child,
+    stdin: Option<ChildStdin>,
+}
+
+/// Starts `$APTLY_PAGER`, `$PAGER`, or `less -R` for the rest of the run when
+/// `enabled` and stdout is a terminal. Falls back to plain stdout, with a
+/// warning, if the pager cannot be spawned.
+pub(crate) fn start(enabled: bool) {
+    if !enabled || !io::stdout().is_terminal() {
+        return;
+    }
+
+    let command = env::var("APTLY_PAGER")
+        .or_else(|_| env::var("PAGER"))
+        .ok()
+        .filter(|command| !command.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_owned());
+    let mut parts = command.split_whitespace();
+    let Some(program) = parts.next() else {
+        return;
+    };
+
+    match Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .spawn()
+    {
+        Ok(mut child) => {
+            let stdin = child.stdin.take();
+            *lock(&PAGER) = Some(Pager { child, stdin });
+        }
+        Err(err) => eprintln!("warning: failed to start pager `{command}`: {err}"),
+    }
+}
+
+/// Sends everything written through [`stdout`] to `path` instead, creating
+/// parent directories as needed. `path` is only replaced by [`commit_output`].
+pub(crate) fn redirect_to_file(path: &Path) -> Result<()> {
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    fs::create_dir_all(parent)
+        .with_context(|| format!("failed to create directory {}", parent.display()))?;
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(".aptly-output");
+    #[cfg(unix)]
+    {
+        // Like `File::create`: 0o666 less the umask, instead of owner-only.
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(fs::Permissions::from_mode(0o666));
+    }
+    let temp = builder
+        .tempfile_in(parent)
+        .with_context(|| format!("failed to create output file {}", path.display()))?;
+    // A file being replaced keeps its mode.
+    if let Ok(metadata) = fs::metadata(path) {
+        fs::set_permissions(temp.path(), metadata.permissions())
+            .with_context(|| format!("failed to set permissions on {}", path.display()))?;
+    }
+    *lock(&OUTPUT_FILE) = Some(OutputFile {
+        temp,
+        path: path.to_owned(),
+    });
+    Ok(())
+}
+
+/// Moves the `--output` temp file into place. Call only once the command
+/// has succeeded; without it, [`finish`] discards the partial output.
+pub(crate) fn commit_output() -> Result<()> {
+    let Some(output) = lock(&OUTPUT_FILE).take() else {
+        return Ok(());
+    };
+    let mut file = output
+        .temp
+        .persist(&output.path)
+        .map_err(|err| err.error)
+        .with_context(|| format!("failed to write output file {}", output.path.display()))?;
+    file.flush()?;
+    Ok(())
+}
+
+/// True when [`stdout`] is a file or pager rather than the process stdout,
+/// so child processes must have their output piped through it.
+pub(crate) fn is_redirected() -> bool {
+    lock(&OUTPUT_FILE).is_some() || lock(&PAGER).is_some()
+}
+
+/// Closes the pager's input and waits for the user to quit it, and drops an
+/// uncommitted `--output` temp file.
+pub(crate) fn finish() {
+    drop(lock(&OUTPUT_FILE).take());
+    let Some(mut pager) = lock(&PAGER).take() else {
+        return;
+    };
+    drop(pager.stdin.take());
+    let _ = pager.child.wait();
+}
+
+/// Stdout, or the `--output` file or pager's input when one is active.
+pub(crate) fn stdout() -> PagedStdout {
+    PagedStdout
+}
+
+pub(crate) struct PagedStdout;
+
+impl Write for PagedStdout {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(output) = lock(&OUTPUT_FILE).as_mut() {
+            return output.temp.write(buf);
+        }
+        let mut pager = lock(&PAGER);
+        match pager.as_mut().and_then(|pager| pager.stdin.as_mut()) {
+            // Quitting the pager early closes the pipe; drop the rest quietly.
+            Some(stdin) => match stdin.write(buf) {
+                Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(buf.len()),
+                result => result,
+            },
+            None => io::stdout().write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(output) = lock(&OUTPUT_FILE).as_mut() {
+            return output.temp.flush();
+        }
+        let mut pager = lock(&PAGER);
+        match pager.as_mut().and_then(|pager| pager.stdin.as_mut()) {
+            Some(stdin) => match stdin.flush() {
+                Err(err) if err.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                result => result,
+            },
+            None => io::stdout().flush(),
+        }
+    }
+}
+
+fn lock<T>(sink: &'static Mutex<Option<T>>) -> MutexGuard<'static, Option<T>> {
+    sink.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
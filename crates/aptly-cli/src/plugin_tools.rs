@@ -1,6 +1,7 @@
 use anyhow::{anyhow, Context, Result};
 use serde::Serialize;
 use std::env;
+use std::io;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
@@ -123,13 +124,7 @@ pub fn doctor_move_decompiler(explicit_bin: Option<&str>) -> PluginDoctorReport
 }
 
 pub fn run_move_decompiler(explicit_bin: Option<&str>, args: &[String]) -> Result<()> {
-    let result = resolve_move_decompiler(explicit_bin);
-    let path = result.path.ok_or_else(|| {
-        anyhow!(
-            "move-decompiler plugin is not installed.\n{}",
-            move_decompiler_install_hint()
-        )
-    })?;
+    let path = resolve_move_decompiler_bin(explicit_bin)?;
 
     if args.is_empty() {
         return Err(anyhow!(
@@ -137,12 +132,31 @@ pub fn run_move_decompiler(explicit_bin: Option<&str>, args: &[String]) -> Resul
         ));
     }
 
-    let status = Command::new(&path)
-        .args(args)
-        .status()
-        .with_context(|| format!("failed to execute {}", path.display()))?;
+    exec_plugin(MOVE_DECOMPILER_BIN, &path, args)
+}
+
+/// Runs the plugin at `path` to completion. Its stdout is inherited, or
+/// piped through [`crate::pager::stdout`] when `--output` or the pager is
+/// active so the plugin's output lands there too.
+fn exec_plugin(name: &str, path: &Path, args: &[String]) -> Result<()> {
+    let mut command = Command::new(path);
+    command.args(args);
+    let status = if crate::pager::is_redirected() {
+        let mut child = command
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to execute {}", path.display()))?;
+        if let Some(mut stdout) = child.stdout.take() {
+            io::copy(&mut stdout, &mut crate::pager::stdout())
+                .with_context(|| format!("failed to copy {name} output"))?;
+        }
+        child.wait()
+    } else {
+        command.status()
+    }
+    .with_context(|| format!("failed to execute {}", path.display()))?;
     if !status.success() {
-        return Err(anyhow!("move-decompiler exited with status {status}"));
+        return Err(anyhow!("{name} exited with status {status}"));
     }
 
     Ok(())
@@ -379,6 +393,54 @@ pub fn resolve_aptos_script_compose_bin(explicit_bin: Option<&str>) -> Result<Pa
     Ok(path)
 }
 
+/// Names accepted by [`run_plugin_passthrough`].
+pub const PLUGIN_NAMES: &[&str] = &[
+    MOVE_DECOMPILER_BIN,
+    APTOS_TRACER_BIN,
+    APTOS_SCRIPT_COMPOSE_BIN,
+];
+
+pub fn resolve_move_decompiler_bin(explicit_bin: Option<&str>) -> Result<PathBuf> {
+    let result = resolve_move_decompiler(explicit_bin);
+    let path = result.path.ok_or_else(|| {
+        anyhow!(
+            "move-decompiler plugin is not installed.\n{}",
+            move_decompiler_install_hint()
+        )
+    })?;
+
+    if !path.is_file() {
+        return Err(anyhow!(
+            "move-decompiler binary was resolved but does not exist: {}",
+            path.display()
+        ));
+    }
+
+    Ok(path)
+}
+
+/// Resolves the plugin `name` through the usual discovery and runs it with
+/// `args`.
+pub fn run_plugin_passthrough(
+    name: &str,
+    explicit_bin: Option<&str>,
+    args: &[String],
+) -> Result<()> {
+    let path = match name {
+        MOVE_DECOMPILER_BIN => resolve_move_decompiler_bin(explicit_bin)?,
+        APTOS_TRACER_BIN => resolve_aptos_tracer_bin(explicit_bin)?,
+        APTOS_SCRIPT_COMPOSE_BIN => resolve_aptos_script_compose_bin(explicit_bin)?,
+        _ => {
+            return Err(anyhow!(
+                "unknown plugin `{name}`; expected one of: {}",
+                PLUGIN_NAMES.join(", ")
+            ))
+        }
+    };
+
+    exec_plugin(name, &path, args)
+}
+
 fn resolve_move_decompiler(explicit_bin: Option<&str>) -> DiscoveryResult {
     if let Some(bin) = explicit_bin {
         if !bin.trim().is_empty() {
@@ -1,7 +1,9 @@
 use anyhow::{anyhow, bail, Context, Result};
-use aptly_aptos::AptosClient;
+use aptly_aptos::{
+    contains_unresolved_type_param, substitute_type_parameters, validate_type_string, AptosClient,
+};
 use aptos_dynamic_transaction_composer::{CallArgument, TransactionComposer};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use move_core_types::{
     account_address::AccountAddress,
     identifier::Identifier,
@@ -10,15 +12,22 @@ use move_core_types::{
     transaction_argument::TransactionArgument,
     value::MoveValue,
 };
+use schemars::{json_schema, schema_for, JsonSchema, Schema, SchemaGenerator};
 use serde::{Deserialize, Deserializer};
 use serde_json::{json, Value};
 use std::{
+    borrow::Cow,
     collections::{BTreeSet, HashMap},
-    io,
+    fmt, fs, io,
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
 const DEFAULT_RPC_URL: &str = "https://rpc.sentio.xyz/aptos/v1";
+const FIXED_POINT32_TYPE: &str = "0x1::fixed_point32::FixedPoint32";
+const FIXED_POINT64_TYPE: &str = "0x1::fixed_point64::FixedPoint64";
+/// Longest fractional part accepted for fixed-point literals; keeps scaling within u128.
+const MAX_FIXED_POINT_FRACTION_DIGITS: usize = 38;
 
 #[derive(Parser)]
 #[command(
@@ -32,16 +41,56 @@ struct Cli {
     with_metadata: bool,
     #[arg(long, default_value_t = false)]
     emit_script_payload: bool,
+    /// Output encoding; `both` prints `{script_bytes_hex, script_payload}`
+    /// from a single compile.
+    #[arg(long, value_enum, conflicts_with = "emit_script_payload")]
+    emit: Option<EmitFormat>,
+    /// Print a human-readable summary of each batched call to stderr.
+    #[arg(long, default_value_t = false)]
+    explain: bool,
+    /// Print the JSON Schema of the payload format and exit.
+    #[arg(long, default_value_t = false)]
+    print_schema: bool,
+    /// Resolve modules from `<addr>_<module>.mv` (plus optional
+    /// `<addr>_<module>.abi.json`) files here before falling back to the RPC.
+    #[arg(long, value_name = "DIR")]
+    modules_dir: Option<PathBuf>,
+    /// Check the payload and list every problem found, then exit without
+    /// contacting the RPC. Return indexes are checked against ABIs in
+    /// `--modules-dir` when present.
+    #[arg(long, default_value_t = false)]
+    validate_only: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum EmitFormat {
+    /// Raw `0x`-prefixed script bytes.
+    Bytes,
+    /// Script payload JSON.
+    ScriptPayload,
+    /// Both, as one JSON object.
+    Both,
+}
+
+/// One batched call in a compose payload.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 struct StepInput {
+    /// Unique name later steps use to reference this step's return values.
     label: String,
+    /// Fully-qualified function id, e.g. `0x1::coin::withdraw`.
     function: String,
+    /// Concrete type arguments, e.g. `0x1::aptos_coin::AptosCoin`.
     #[serde(default, rename = "typeArguments", alias = "type_arguments")]
     type_arguments: Vec<String>,
+    /// Label of an earlier step whose resolved type arguments this step reuses.
+    #[serde(default, rename = "typeArgumentsFrom", alias = "type_arguments_from")]
+    type_arguments_from: Option<String>,
+    /// Arguments in function parameter order.
     args: Vec<ArgInput>,
+    /// Free-form annotation for humans maintaining the payload; never encoded.
+    #[serde(default, alias = "note")]
+    description: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -51,6 +100,32 @@ enum ArgInput {
     Ref { step: String, return_index: usize },
 }
 
+impl ArgInput {
+    fn kind_name(&self) -> &'static str {
+        match self {
+            ArgInput::Signer => "signer",
+            ArgInput::Literal { .. } => "literal",
+            ArgInput::Ref { .. } => "ref",
+        }
+    }
+}
+
+impl JsonSchema for ArgInput {
+    fn schema_name() -> Cow<'static, str> {
+        "ArgInput".into()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "oneOf": [
+                generator.subschema_for::<SignerArgInput>(),
+                generator.subschema_for::<LiteralArgInput>(),
+                generator.subschema_for::<RefArgInput>(),
+            ]
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, Deserialize)]
 #[serde(rename_all = "snake_case")]
 enum ArgInputKind {
@@ -64,45 +139,53 @@ struct ArgInputKindOnly {
     kind: ArgInputKind,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 enum SignerArgKind {
     Signer,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 enum LiteralArgKind {
     Literal,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 enum RefArgKind {
     Ref,
 }
 
-#[derive(Debug, Deserialize)]
+/// Passes the transaction signer.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 struct SignerArgInput {
     #[serde(rename = "kind")]
     _kind: SignerArgKind,
 }
 
-#[derive(Debug, Deserialize)]
+/// Passes a JSON literal encoded against the expected parameter type.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 struct LiteralArgInput {
     #[serde(rename = "kind")]
     _kind: LiteralArgKind,
+    /// Number, string, bool, or array depending on the parameter type.
+    /// Any `vector<u8>`-backed type, including `String`, also takes raw bytes
+    /// as `{"bytes": "0x.."}`.
     value: Value,
 }
 
-#[derive(Debug, Deserialize)]
+/// Passes a return value of an earlier step.
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(deny_unknown_fields)]
 struct RefArgInput {
     #[serde(rename = "kind")]
     _kind: RefArgKind,
+    /// Label of the earlier step.
     step: String,
+    /// Zero-based index into that step's return values.
     #[serde(rename = "returnIndex", alias = "return_index")]
     return_index: usize,
 }
@@ -140,7 +223,9 @@ struct ResolvedStep {
     label: String,
     function_id: FunctionId,
     type_arguments: Vec<String>,
+    type_arguments_from: Option<String>,
     args: Vec<ArgInput>,
+    description: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -153,6 +238,8 @@ struct FunctionId {
 struct ModuleInfo {
     bytecode: Vec<u8>,
     functions: HashMap<String, Vec<String>>,
+    type_param_counts: HashMap<String, usize>,
+    return_counts: HashMap<String, usize>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -172,7 +259,11 @@ struct RpcModuleAbi {
 struct RpcFunctionAbi {
     name: String,
     #[serde(default)]
+    generic_type_params: Vec<Value>,
+    #[serde(default)]
     params: Vec<String>,
+    #[serde(default, rename = "return")]
+    return_types: Vec<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -184,61 +275,210 @@ struct SerializedScriptOutput {
     args: Vec<TransactionArgument>,
 }
 
+/// Resolves module bytecode and ABI for the modules a payload touches.
+trait ModuleSource {
+    fn module_info(&self, module_id: &ModuleId) -> Result<ModuleInfo>;
+}
+
+impl ModuleSource for AptosClient {
+    fn module_info(&self, module_id: &ModuleId) -> Result<ModuleInfo> {
+        fetch_module_info(self, module_id)
+    }
+}
+
+/// Serves modules from local files, falling back to `rpc` when one is missing.
+struct DirModuleSource {
+    dir: PathBuf,
+    rpc: Option<AptosClient>,
+}
+
+impl ModuleSource for DirModuleSource {
+    fn module_info(&self, module_id: &ModuleId) -> Result<ModuleInfo> {
+        if let Some(info) = read_local_module_info(&self.dir, module_id)? {
+            return Ok(info);
+        }
+        let not_local = || {
+            format!(
+                "not found in {} as {}_{}.mv",
+                self.dir.display(),
+                module_id.address().to_hex_literal(),
+                module_id.name()
+            )
+        };
+        match &self.rpc {
+            Some(client) => fetch_module_info(client, module_id)
+                .with_context(|| format!("{} and RPC fallback failed", not_local())),
+            None => Err(anyhow!(not_local())),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ComposedScript {
+    script_bytes: Vec<u8>,
+    /// Literal arguments for `--emit script-payload`. Kept as an error
+    /// rather than failing `compose`, since raw script bytes can carry
+    /// literals (such as non-UTF-8 strings) that payload JSON cannot.
+    payload_arguments: Result<Vec<Value>>,
+    /// One human-readable block per batched call, for `--explain`.
+    call_summaries: Vec<String>,
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
     run(cli)
 }
 
 fn run(cli: Cli) -> Result<()> {
-    let payload_steps = read_payload_from_stdin()?;
+    if cli.print_schema {
+        println!("{}", serde_json::to_string_pretty(&payload_schema())?);
+        return Ok(());
+    }
+
+    let raw = read_raw_payload_from_stdin()?;
+    if cli.validate_only {
+        let step_count = validate_payload(raw, cli.modules_dir.as_deref())?;
+        eprintln!("payload is valid: {step_count} step(s)");
+        return Ok(());
+    }
+
+    let payload_steps = parse_steps_payload(raw)?;
+    let client = AptosClient::new(&cli.rpc_url)?;
+    let composed = match cli.modules_dir {
+        Some(dir) => {
+            let source = DirModuleSource {
+                dir,
+                rpc: Some(client),
+            };
+            compose(payload_steps, &source, cli.with_metadata)?
+        }
+        None => compose(payload_steps, &client, cli.with_metadata)?,
+    };
+    if cli.explain {
+        for summary in &composed.call_summaries {
+            eprintln!("{summary}");
+        }
+    }
+
+    let emit = cli.emit.unwrap_or(if cli.emit_script_payload {
+        EmitFormat::ScriptPayload
+    } else {
+        EmitFormat::Bytes
+    });
+    match emit {
+        EmitFormat::Bytes => println!("{}", script_bytes_hex(&composed)),
+        EmitFormat::ScriptPayload => {
+            let payload = script_payload_json(&composed)?;
+            println!("{}", serde_json::to_string_pretty(&payload)?);
+        }
+        EmitFormat::Both => {
+            let output = both_encodings_json(&composed)?;
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+    }
+
+    Ok(())
+}
+
+fn script_bytes_hex(composed: &ComposedScript) -> String {
+    format!("0x{}", hex::encode(&composed.script_bytes))
+}
+
+fn both_encodings_json(composed: &ComposedScript) -> Result<Value> {
+    Ok(json!({
+        "script_bytes_hex": script_bytes_hex(composed),
+        "script_payload": script_payload_json(composed)?
+    }))
+}
+
+/// JSON Schema for the top-level step array, derived from the payload structs.
+fn payload_schema() -> Schema {
+    schema_for!(Vec<StepInput>)
+}
+
+fn compose(
+    payload_steps: Vec<StepInput>,
+    module_source: &dyn ModuleSource,
+    with_metadata: bool,
+) -> Result<ComposedScript> {
     let steps = resolve_steps(payload_steps)?;
     let required_modules = collect_required_modules(&steps)?;
 
-    let client = AptosClient::new(&cli.rpc_url)?;
     let mut composer = TransactionComposer::single_signer();
     let mut modules = HashMap::new();
+    let mut missing = Vec::new();
 
     for module_id in required_modules {
-        let module_info = fetch_module_info(&client, &module_id)?;
+        let module_info = match module_source.module_info(&module_id) {
+            Ok(module_info) => module_info,
+            Err(err) => {
+                missing.push(format!("{module_id}: {err:#}"));
+                continue;
+            }
+        };
         composer
             .store_module(module_info.bytecode.clone())
             .map_err(|err| anyhow!("failed to load module {} into composer: {err}", module_id))?;
         modules.insert(module_id, module_info);
     }
+    if !missing.is_empty() {
+        bail!(
+            "failed to resolve {} module(s):\n  {}",
+            missing.len(),
+            missing.join("\n  ")
+        );
+    }
+    check_ref_return_arity(&steps, &modules)?;
 
     let mut returns_by_label: HashMap<String, Vec<CallArgument>> = HashMap::new();
-    let mut payload_arguments: Vec<Value> = Vec::new();
+    let mut payload_arguments: Result<Vec<Value>> = Ok(Vec::new());
+    let mut call_summaries = Vec::with_capacity(steps.len());
     for step in steps {
         let module_info = modules
             .get(&step.function_id.module_id)
             .ok_or_else(|| anyhow!("module {} was not loaded", step.function_id.module_id))?;
+        check_type_argument_count(&step, module_info)?;
         let expected_params = resolve_function_params(&step, module_info)?;
         if expected_params.len() != step.args.len() {
+            let provided: Vec<&str> = step.args.iter().map(ArgInput::kind_name).collect();
             bail!(
-                "step `{}` argument count mismatch: function expects {}, payload provides {}",
+                "step `{}` argument count mismatch: function expects {} ({}), payload provides {} ({})",
                 step.label,
                 expected_params.len(),
-                step.args.len()
+                expected_params.join(", "),
+                step.args.len(),
+                provided.join(", ")
             );
         }
 
+        check_signer_positions(&step, &expected_params)?;
+        call_summaries.push(summarize_call(&step, &expected_params));
+
         let mut args = Vec::with_capacity(step.args.len());
         for (index, (arg, expected_param)) in
             step.args.iter().zip(expected_params.iter()).enumerate()
         {
             let call_arg = match arg {
                 ArgInput::Signer => {
-                    let expected = normalize_type_name(expected_param);
-                    if expected != "&signer" {
+                    if !is_signer_param(expected_param) {
                         bail!(
-                            "step `{}` arg {} uses `signer` but expected parameter type is `{}`",
+                            "step `{}` arg {} uses `signer` but expected parameter type is `{}` (normalized `{}`)",
                             step.label,
                             index,
-                            expected_param
+                            expected_param,
+                            normalize_type_name(expected_param)
                         );
                     }
                     CallArgument::new_signer(0)
                 }
+                ArgInput::Literal { .. } if is_signer_param(expected_param) => {
+                    bail!(
+                        "step `{}` arg {} expects a signer (normalized `{}`); pass {{\"kind\":\"signer\"}} instead of a literal",
+                        step.label,
+                        index,
+                        normalize_type_name(expected_param)
+                    );
+                }
                 ArgInput::Literal { value } => {
                     let bytes = encode_literal(expected_param, value).with_context(|| {
                         format!(
@@ -246,16 +486,17 @@ fn run(cli: Cli) -> Result<()> {
                             step.label, index, expected_param
                         )
                     })?;
-                    payload_arguments.push(
-                        normalize_literal_for_script_payload(expected_param, value).with_context(
-                            || {
-                                format!(
-                                    "failed to normalize literal for script payload in step `{}` arg {}",
-                                    step.label, index
-                                )
-                            },
-                        )?,
-                    );
+                    let normalized = normalize_literal_for_script_payload(expected_param, value)
+                        .with_context(|| {
+                            format!(
+                                "failed to normalize literal for script payload in step `{}` arg {}",
+                                step.label, index
+                            )
+                        });
+                    payload_arguments = payload_arguments.and_then(|mut arguments| {
+                        arguments.push(normalized?);
+                        Ok(arguments)
+                    });
                     CallArgument::new_bytes(bytes)
                 }
                 ArgInput::Ref {
@@ -304,45 +545,113 @@ fn run(cli: Cli) -> Result<()> {
     }
 
     let script_bytes = composer
-        .generate_batched_calls(cli.with_metadata)
+        .generate_batched_calls(with_metadata)
         .map_err(|err| anyhow!("failed to generate batched script: {err}"))?;
 
-    if cli.emit_script_payload {
-        let script: SerializedScriptOutput =
-            bcs::from_bytes(&script_bytes).context("failed to decode generated script output")?;
-        if script.args.len() != payload_arguments.len() {
+    Ok(ComposedScript {
+        script_bytes,
+        payload_arguments,
+        call_summaries,
+    })
+}
+
+/// Move entry functions take their signers first: `signer` args must fill
+/// exactly the leading signer parameters and appear nowhere else.
+fn check_signer_positions(step: &ResolvedStep, expected_params: &[String]) -> Result<()> {
+    let leading_signers = expected_params
+        .iter()
+        .take_while(|param| is_signer_param(param))
+        .count();
+    for (index, arg) in step.args.iter().enumerate() {
+        let is_signer_arg = matches!(arg, ArgInput::Signer);
+        if is_signer_arg != (index < leading_signers) {
             bail!(
-                "generated script argument count mismatch: script has {} argument(s), normalized payload has {}",
-                script.args.len(),
-                payload_arguments.len()
+                "step `{}` arg {} is {} but the function takes {} leading signer parameter(s) ({})",
+                step.label,
+                index,
+                if is_signer_arg {
+                    "a signer"
+                } else {
+                    "not a signer"
+                },
+                leading_signers,
+                expected_params.join(", ")
             );
         }
-        let type_arguments: Vec<String> = script
-            .ty_args
-            .iter()
-            .map(TypeTag::to_canonical_string)
-            .collect();
-        let payload = json!({
-            "type": "script_payload",
-            "code": {
-                "bytecode": format!("0x{}", hex::encode(script.code))
-            },
-            "type_arguments": type_arguments,
-            "arguments": payload_arguments
-        });
-        println!("{}", serde_json::to_string_pretty(&payload)?);
-    } else {
-        println!("0x{}", hex::encode(script_bytes));
     }
-
     Ok(())
 }
 
-fn read_payload_from_stdin() -> Result<Vec<StepInput>> {
+fn summarize_call(step: &ResolvedStep, expected_params: &[String]) -> String {
+    let mut summary = format!(
+        "step `{}`: {}",
+        step.label,
+        step.function_id.fully_qualified()
+    );
+    if !step.type_arguments.is_empty() {
+        summary.push_str(&format!("<{}>", step.type_arguments.join(", ")));
+    }
+    if let Some(description) = &step.description {
+        summary.push_str(&format!(" -- {description}"));
+    }
+    for (index, (arg, expected_param)) in step.args.iter().zip(expected_params).enumerate() {
+        let shape = match arg {
+            ArgInput::Signer => "signer".to_owned(),
+            ArgInput::Literal { value } => format!("literal {}", describe_literal_shape(value)),
+            ArgInput::Ref {
+                step: ref_step,
+                return_index,
+            } => format!("ref `{ref_step}` return {return_index}"),
+        };
+        summary.push_str(&format!("\n  arg {index}: {expected_param} <- {shape}"));
+    }
+    summary
+}
+
+fn describe_literal_shape(value: &Value) -> String {
+    match value {
+        Value::Array(items) => format!("array of {} item(s)", items.len()),
+        Value::Object(fields) => {
+            let keys: Vec<&str> = fields.keys().map(String::as_str).collect();
+            format!("object {{{}}}", keys.join(", "))
+        }
+        other => other.to_string(),
+    }
+}
+
+fn script_payload_json(composed: &ComposedScript) -> Result<Value> {
+    let arguments = composed
+        .payload_arguments
+        .as_ref()
+        .map_err(|err| anyhow!("{err:#}"))?;
+    let script: SerializedScriptOutput = bcs::from_bytes(&composed.script_bytes)
+        .context("failed to decode generated script output")?;
+    if script.args.len() != arguments.len() {
+        bail!(
+            "generated script argument count mismatch: script has {} argument(s), normalized payload has {}",
+            script.args.len(),
+            arguments.len()
+        );
+    }
+    let type_arguments: Vec<String> = script
+        .ty_args
+        .iter()
+        .map(TypeTag::to_canonical_string)
+        .collect();
+    Ok(json!({
+        "type": "script_payload",
+        "code": {
+            "bytecode": format!("0x{}", hex::encode(script.code))
+        },
+        "type_arguments": type_arguments,
+        "arguments": arguments
+    }))
+}
+
+fn read_raw_payload_from_stdin() -> Result<Value> {
     let stdin = io::stdin();
-    let raw: Value = serde_json::from_reader(stdin.lock())
-        .context("failed to parse script compose payload JSON from stdin")?;
-    parse_steps_payload(raw)
+    serde_json::from_reader(stdin.lock())
+        .context("failed to parse script compose payload JSON from stdin")
 }
 
 fn parse_steps_payload(raw: Value) -> Result<Vec<StepInput>> {
@@ -353,54 +662,238 @@ fn parse_steps_payload(raw: Value) -> Result<Vec<StepInput>> {
     }
 }
 
+/// Runs the same checks as [`validate_payload`] (without ABI lookups) and
+/// fails with every problem found, then resolves `typeArgumentsFrom`.
 fn resolve_steps(payload_steps: Vec<StepInput>) -> Result<Vec<ResolvedStep>> {
     if payload_steps.is_empty() {
         bail!("payload must include at least one step");
     }
 
-    let mut resolved = Vec::with_capacity(payload_steps.len());
-    let mut labels: HashMap<String, usize> = HashMap::new();
+    let steps: Vec<(usize, StepInput)> = payload_steps.into_iter().enumerate().collect();
+    let issues = collect_step_issues(&steps, &|_: &FunctionId| None);
+    if !issues.is_empty() {
+        return Err(payload_issues_error(&issues));
+    }
 
-    for (index, step) in payload_steps.into_iter().enumerate() {
+    let mut resolved: Vec<ResolvedStep> = Vec::with_capacity(steps.len());
+    let mut labels: HashMap<String, usize> = HashMap::new();
+    for (index, step) in steps {
         let label = step.label.trim().to_owned();
-        if label.is_empty() {
-            bail!("step at index {index} has an empty label");
+        let function_id = FunctionId::parse(&step.function)
+            .with_context(|| format!("invalid function id in step `{label}`"))?;
+        // Both lookups were checked by `collect_step_issues`.
+        let type_arguments = match &step.type_arguments_from {
+            Some(source) => resolved[labels[source]].type_arguments.clone(),
+            None => step.type_arguments,
+        };
+
+        labels.insert(label.clone(), index);
+        resolved.push(ResolvedStep {
+            label,
+            function_id,
+            type_arguments,
+            type_arguments_from: step.type_arguments_from,
+            args: step.args,
+            description: step.description,
+        });
+    }
+
+    Ok(resolved)
+}
+
+/// One problem found by [`validate_payload`], located by step and argument
+/// index.
+#[derive(Debug, PartialEq, Eq)]
+struct PayloadIssue {
+    step: usize,
+    arg: Option<usize>,
+    message: String,
+}
+
+impl PayloadIssue {
+    fn step(step: usize, message: impl Into<String>) -> Self {
+        Self {
+            step,
+            arg: None,
+            message: message.into(),
         }
-        if labels.contains_key(&label) {
-            bail!("duplicate step label `{label}`");
+    }
+
+    fn arg(step: usize, arg: usize, message: impl Into<String>) -> Self {
+        Self {
+            step,
+            arg: Some(arg),
+            message: message.into(),
         }
+    }
+}
 
-        let function_id = FunctionId::parse(&step.function)
-            .with_context(|| format!("invalid function id in step `{label}`"))?;
+impl fmt::Display for PayloadIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.arg {
+            Some(arg) => write!(f, "step {} arg {}: {}", self.step, arg, self.message),
+            None => write!(f, "step {}: {}", self.step, self.message),
+        }
+    }
+}
+
+/// Checks the whole payload offline and fails with every problem found
+/// rather than the first. Returns the step count when the payload is clean.
+fn validate_payload(raw: Value, modules_dir: Option<&Path>) -> Result<usize> {
+    let Value::Array(items) = raw else {
+        bail!("invalid payload shape: expected top-level step array `[...]`");
+    };
+    if items.is_empty() {
+        bail!("payload must include at least one step");
+    }
+
+    let step_count = items.len();
+    let mut issues = Vec::new();
+    let mut steps = Vec::with_capacity(step_count);
+    for (index, item) in items.into_iter().enumerate() {
+        match serde_json::from_value::<StepInput>(item) {
+            Ok(step) => steps.push((index, step)),
+            Err(err) => issues.push(PayloadIssue::step(index, err.to_string())),
+        }
+    }
+    let return_count = |function_id: &FunctionId| {
+        let info = read_local_module_info(modules_dir?, &function_id.module_id).ok()??;
+        info.return_counts.get(&function_id.function).copied()
+    };
+    issues.extend(collect_step_issues(&steps, &return_count));
+
+    if !issues.is_empty() {
+        return Err(payload_issues_error(&issues));
+    }
+    Ok(step_count)
+}
 
+fn payload_issues_error(issues: &[PayloadIssue]) -> anyhow::Error {
+    let lines: Vec<String> = issues.iter().map(ToString::to_string).collect();
+    anyhow!(
+        "payload has {} problem(s):\n  {}",
+        issues.len(),
+        lines.join("\n  ")
+    )
+}
+
+/// The checks behind [`validate_payload`] for steps that deserialized.
+/// `return_count` reports how many values a function returns when its ABI
+/// is available without the RPC.
+fn collect_step_issues(
+    steps: &[(usize, StepInput)],
+    return_count: &dyn Fn(&FunctionId) -> Option<usize>,
+) -> Vec<PayloadIssue> {
+    let mut issues = Vec::new();
+    let positions: HashMap<&str, usize> = steps
+        .iter()
+        .rev()
+        .map(|(index, step)| (step.label.trim(), *index))
+        .collect();
+    let mut seen: HashMap<&str, Option<usize>> = HashMap::new();
+
+    for (index, step) in steps {
+        let index = *index;
+        let label = step.label.trim();
+        if label.is_empty() {
+            issues.push(PayloadIssue::step(index, "`label` is empty"));
+        } else if seen.contains_key(label) {
+            issues.push(PayloadIssue::step(
+                index,
+                format!("duplicate step label `{label}`"),
+            ));
+        }
+
+        let function_id = if step.function.trim().is_empty() {
+            issues.push(PayloadIssue::step(
+                index,
+                "`function` is empty; expected `<address>::<module>::<function>`",
+            ));
+            None
+        } else {
+            match FunctionId::parse(&step.function) {
+                Ok(function_id) => Some(function_id),
+                Err(err) => {
+                    issues.push(PayloadIssue::step(index, format!("{err:#}")));
+                    None
+                }
+            }
+        };
+
+        if let Some(source) = &step.type_arguments_from {
+            if !step.type_arguments.is_empty() {
+                issues.push(PayloadIssue::step(
+                    index,
+                    "sets both `typeArguments` and `typeArgumentsFrom`",
+                ));
+            }
+            if !seen.contains_key(source.as_str()) {
+                issues.push(PayloadIssue::step(
+                    index,
+                    format!(
+                        "`typeArgumentsFrom` is `{source}`; expected the label of a previous step"
+                    ),
+                ));
+            }
+        }
         for type_argument in &step.type_arguments {
-            TypeTag::from_str(type_argument).with_context(|| {
-                format!("invalid type argument `{type_argument}` in step `{label}`")
-            })?;
+            let checked = validate_type_string(type_argument).and_then(|()| {
+                TypeTag::from_str(type_argument)
+                    .map(drop)
+                    .with_context(|| format!("invalid type argument `{type_argument}`"))
+            });
+            if let Err(err) = checked {
+                issues.push(PayloadIssue::step(index, format!("{err:#}")));
+            }
         }
 
         for (arg_index, arg) in step.args.iter().enumerate() {
-            if let ArgInput::Ref { step: ref_step, .. } = arg {
-                if !labels.contains_key(ref_step) {
-                    bail!(
-                        "step `{label}` arg {} references `{}`. refs must point to a previous step label",
+            let ArgInput::Ref {
+                step: ref_step,
+                return_index,
+            } = arg
+            else {
+                continue;
+            };
+            let expected = "expected the label of a previous step";
+            match seen.get(ref_step.as_str()) {
+                Some(Some(count)) if return_index >= count => {
+                    issues.push(PayloadIssue::arg(
+                        index,
                         arg_index,
-                        ref_step
-                    );
+                        format!(
+                            "return index {return_index} of `{ref_step}` is out of range; it returns {count} value{}",
+                            if *count == 1 { "" } else { "s" }
+                        ),
+                    ));
                 }
+                Some(_) => {}
+                None if ref_step.as_str() == label => issues.push(PayloadIssue::arg(
+                    index,
+                    arg_index,
+                    format!("references its own step `{ref_step}`; {expected}"),
+                )),
+                None => match positions.get(ref_step.as_str()) {
+                    Some(position) => issues.push(PayloadIssue::arg(
+                        index,
+                        arg_index,
+                        format!("references `{ref_step}` (step {position}), which is defined later; {expected}"),
+                    )),
+                    None => issues.push(PayloadIssue::arg(
+                        index,
+                        arg_index,
+                        format!("references unknown step `{ref_step}`; {expected}"),
+                    )),
+                },
             }
         }
 
-        labels.insert(label.clone(), index);
-        resolved.push(ResolvedStep {
-            label,
-            function_id,
-            type_arguments: step.type_arguments,
-            args: step.args,
-        });
+        if !label.is_empty() {
+            seen.entry(label)
+                .or_insert_with(|| function_id.as_ref().and_then(return_count));
+        }
     }
-
-    Ok(resolved)
+    issues
 }
 
 fn collect_required_modules(steps: &[ResolvedStep]) -> Result<BTreeSet<ModuleId>> {
@@ -442,17 +935,129 @@ fn fetch_module_info(client: &AptosClient, module_id: &ModuleId) -> Result<Modul
     let bytecode = hex::decode(bytecode_hex)
         .with_context(|| format!("failed to decode bytecode for module {}", module_id))?;
 
+    Ok(module_info_from_parts(bytecode, module.abi))
+}
+
+/// Reads `<addr>_<module>.mv` and, if present, `<addr>_<module>.abi.json`
+/// (the `abi` object of the node's module response). Returns `None` when
+/// there is no bytecode file for the module.
+fn read_local_module_info(dir: &Path, module_id: &ModuleId) -> Result<Option<ModuleInfo>> {
+    let stem = format!(
+        "{}_{}",
+        module_id.address().to_hex_literal(),
+        module_id.name()
+    );
+    let bytecode_path = dir.join(format!("{stem}.mv"));
+    if !bytecode_path.is_file() {
+        return Ok(None);
+    }
+    let bytecode = fs::read(&bytecode_path)
+        .with_context(|| format!("failed to read {}", bytecode_path.display()))?;
+
+    let abi_path = dir.join(format!("{stem}.abi.json"));
+    let abi = if abi_path.is_file() {
+        let raw = fs::read_to_string(&abi_path)
+            .with_context(|| format!("failed to read {}", abi_path.display()))?;
+        Some(
+            serde_json::from_str::<RpcModuleAbi>(&raw)
+                .with_context(|| format!("failed to parse ABI {}", abi_path.display()))?,
+        )
+    } else {
+        None
+    };
+
+    Ok(Some(module_info_from_parts(bytecode, abi)))
+}
+
+fn module_info_from_parts(bytecode: Vec<u8>, abi: Option<RpcModuleAbi>) -> ModuleInfo {
     let mut functions = HashMap::new();
-    if let Some(abi) = module.abi {
+    let mut type_param_counts = HashMap::new();
+    let mut return_counts = HashMap::new();
+    if let Some(abi) = abi {
         for function in abi.exposed_functions {
+            type_param_counts.insert(function.name.clone(), function.generic_type_params.len());
+            return_counts.insert(function.name.clone(), function.return_types.len());
             functions.insert(function.name, function.params);
         }
     }
 
-    Ok(ModuleInfo {
+    ModuleInfo {
         bytecode,
         functions,
-    })
+        type_param_counts,
+        return_counts,
+    }
+}
+
+/// Rejects refs to return indexes the referenced function's ABI does not
+/// produce, before any call is handed to the composer.
+fn check_ref_return_arity(
+    steps: &[ResolvedStep],
+    modules: &HashMap<ModuleId, ModuleInfo>,
+) -> Result<()> {
+    let mut return_counts: HashMap<&str, usize> = HashMap::new();
+    for step in steps {
+        for arg in &step.args {
+            let ArgInput::Ref {
+                step: ref_step,
+                return_index,
+            } = arg
+            else {
+                continue;
+            };
+            let Some(&count) = return_counts.get(ref_step.as_str()) else {
+                continue;
+            };
+            if *return_index >= count {
+                bail!(
+                    "step `{}` references return index {} of `{}` which returns {} value{}",
+                    step.label,
+                    return_index,
+                    ref_step,
+                    count,
+                    if count == 1 { "" } else { "s" }
+                );
+            }
+        }
+
+        let count = modules
+            .get(&step.function_id.module_id)
+            .and_then(|module_info| module_info.return_counts.get(&step.function_id.function));
+        if let Some(&count) = count {
+            return_counts.insert(&step.label, count);
+        }
+    }
+    Ok(())
+}
+
+fn check_type_argument_count(step: &ResolvedStep, module_info: &ModuleInfo) -> Result<()> {
+    let Some(expected) = module_info
+        .type_param_counts
+        .get(&step.function_id.function)
+        .copied()
+    else {
+        return Ok(());
+    };
+    if expected == step.type_arguments.len() {
+        return Ok(());
+    }
+
+    match &step.type_arguments_from {
+        Some(source) => bail!(
+            "step `{}` takes {} type argument(s) from `{}` but `{}` expects {}",
+            step.label,
+            step.type_arguments.len(),
+            source,
+            step.function_id.function,
+            expected
+        ),
+        None => bail!(
+            "step `{}` type argument count mismatch: function expects {}, payload provides {}",
+            step.label,
+            expected,
+            step.type_arguments.len()
+        ),
+    }
 }
 
 fn resolve_function_params(step: &ResolvedStep, module_info: &ModuleInfo) -> Result<Vec<String>> {
@@ -482,84 +1087,6 @@ fn resolve_function_params(step: &ResolvedStep, module_info: &ModuleInfo) -> Res
     Ok(resolved)
 }
 
-fn substitute_type_parameters(param: &str, type_arguments: &[String]) -> String {
-    let chars: Vec<char> = param.chars().collect();
-    let mut resolved = String::with_capacity(param.len());
-    let mut i = 0;
-
-    while i < chars.len() {
-        if let Some((digits_start, end)) = type_param_placeholder_span(&chars, i) {
-            let index: Option<usize> = chars[digits_start..end]
-                .iter()
-                .collect::<String>()
-                .parse::<usize>()
-                .ok();
-            if let Some(type_arg) = index.and_then(|value| type_arguments.get(value)) {
-                resolved.push_str(type_arg);
-            } else {
-                for ch in &chars[i..end] {
-                    resolved.push(*ch);
-                }
-            }
-            i = end;
-            continue;
-        }
-
-        resolved.push(chars[i]);
-        i += 1;
-    }
-
-    resolved
-}
-
-fn contains_unresolved_type_param(param: &str) -> bool {
-    let chars: Vec<char> = param.chars().collect();
-    let mut i = 0;
-
-    while i < chars.len() {
-        if let Some((_, end)) = type_param_placeholder_span(&chars, i) {
-            if end > i {
-                return true;
-            }
-            i = end;
-            continue;
-        }
-        i += 1;
-    }
-
-    false
-}
-
-fn type_param_placeholder_span(chars: &[char], start: usize) -> Option<(usize, usize)> {
-    if chars.get(start).copied()? != 'T' {
-        return None;
-    }
-    if !chars.get(start + 1).is_some_and(char::is_ascii_digit) {
-        return None;
-    }
-
-    let prev_ok = start == 0 || !is_type_param_ident_char(chars[start - 1]);
-    if !prev_ok {
-        return None;
-    }
-
-    let mut end = start + 1;
-    while chars.get(end).is_some_and(char::is_ascii_digit) {
-        end += 1;
-    }
-
-    let next_ok = end == chars.len() || !is_type_param_ident_char(chars[end]);
-    if !next_ok {
-        return None;
-    }
-
-    Some((start + 1, end))
-}
-
-fn is_type_param_ident_char(ch: char) -> bool {
-    ch.is_ascii_alphanumeric() || ch == '_'
-}
-
 fn encode_literal(expected_param: &str, value: &Value) -> Result<Vec<u8>> {
     let mut expected = normalize_type_name(expected_param);
     if expected.starts_with("&mut") {
@@ -572,32 +1099,49 @@ fn encode_literal(expected_param: &str, value: &Value) -> Result<Vec<u8>> {
         bail!("unsupported literal parameter type `{expected_param}`");
     }
 
-    match expected.as_str() {
-        "bool" => serialize_move_value(MoveValue::Bool(parse_bool_literal(value)?)),
-        "u8" => serialize_move_value(MoveValue::U8(parse_number(value, "u8")?)),
-        "u16" => serialize_move_value(MoveValue::U16(parse_number(value, "u16")?)),
-        "u32" => serialize_move_value(MoveValue::U32(parse_number(value, "u32")?)),
-        "u64" => serialize_move_value(MoveValue::U64(parse_number(value, "u64")?)),
-        "u128" => serialize_move_value(MoveValue::U128(parse_number(value, "u128")?)),
-        "u256" => serialize_move_value(MoveValue::U256(parse_number(value, "u256")?)),
-        "i8" => serialize_move_value(MoveValue::I8(parse_number(value, "i8")?)),
-        "i16" => serialize_move_value(MoveValue::I16(parse_number(value, "i16")?)),
-        "i32" => serialize_move_value(MoveValue::I32(parse_number(value, "i32")?)),
-        "i64" => serialize_move_value(MoveValue::I64(parse_number(value, "i64")?)),
-        "i128" => serialize_move_value(MoveValue::I128(parse_number(value, "i128")?)),
-        "i256" => serialize_move_value(MoveValue::I256(parse_number(value, "i256")?)),
-        "address" => serialize_move_value(MoveValue::Address(parse_address_literal(value)?)),
-        "vector<u8>" => serialize_move_value(MoveValue::vector_u8(parse_bytes_literal(value)?)),
-        _ if is_object_type(&expected) => {
-            // Object<T> is a single-field wrapper over address.
-            serialize_move_value(MoveValue::Address(parse_address_literal(value)?))
-        }
-        _ if is_string_wrapper_type(&expected) => {
-            let string = parse_string_literal(value)?;
-            serialize_move_value(MoveValue::vector_u8(string.into_bytes()))
+    serialize_move_value(literal_move_value(expected_param, &expected, value)?)
+}
+
+/// Builds the `MoveValue` for a literal of the (normalized, dereferenced)
+/// `expected` type. `expected_param` is only used in error messages.
+fn literal_move_value(expected_param: &str, expected: &str, value: &Value) -> Result<MoveValue> {
+    Ok(match expected {
+        "bool" => MoveValue::Bool(parse_bool_literal(value)?),
+        "u8" => MoveValue::U8(parse_number(value, "u8")?),
+        "u16" => MoveValue::U16(parse_number(value, "u16")?),
+        "u32" => MoveValue::U32(parse_number(value, "u32")?),
+        "u64" => MoveValue::U64(parse_number(value, "u64")?),
+        "u128" => MoveValue::U128(parse_number(value, "u128")?),
+        "u256" => MoveValue::U256(parse_number(value, "u256")?),
+        "i8" => MoveValue::I8(parse_number(value, "i8")?),
+        "i16" => MoveValue::I16(parse_number(value, "i16")?),
+        "i32" => MoveValue::I32(parse_number(value, "i32")?),
+        "i64" => MoveValue::I64(parse_number(value, "i64")?),
+        "i128" => MoveValue::I128(parse_number(value, "i128")?),
+        "i256" => MoveValue::I256(parse_number(value, "i256")?),
+        "address" => MoveValue::Address(parse_address_literal(value)?),
+        "vector<u8>" => MoveValue::vector_u8(parse_bytes_literal(value)?),
+        // Fixed-point types are single-field wrappers over their raw integer.
+        FIXED_POINT32_TYPE => MoveValue::U64(parse_fixed_point32_literal(value)?),
+        FIXED_POINT64_TYPE => MoveValue::U128(parse_fixed_point64_literal(value)?),
+        // Object<T> is a single-field wrapper over address.
+        _ if is_object_type(expected) => MoveValue::Address(parse_address_literal(value)?),
+        _ if is_object_vector_type(expected) => MoveValue::Vector(
+            parse_object_vector_literal(value)?
+                .into_iter()
+                .map(MoveValue::Address)
+                .collect(),
+        ),
+        _ if is_string_wrapper_type(expected) => {
+            MoveValue::vector_u8(parse_string_wrapper_bytes(value)?)
         }
-        _ => bail!("unsupported literal parameter type `{expected_param}`"),
-    }
+        _ => match vector_element_type(expected) {
+            Some(element) => MoveValue::Vector(map_vector_literal(expected, value, |item| {
+                literal_move_value(expected_param, element, item)
+            })?),
+            None => bail!("unsupported literal parameter type `{expected_param}`"),
+        },
+    })
 }
 
 fn normalize_literal_for_script_payload(expected_param: &str, value: &Value) -> Result<Value> {
@@ -645,22 +1189,84 @@ fn normalize_literal_for_script_payload(expected_param: &str, value: &Value) ->
             "0x{}",
             hex::encode(parse_bytes_literal(value)?)
         ))),
+        FIXED_POINT32_TYPE => Ok(json!({
+            "value": parse_fixed_point32_literal(value)?.to_string()
+        })),
+        FIXED_POINT64_TYPE => Ok(json!({
+            "value": parse_fixed_point64_literal(value)?.to_string()
+        })),
         _ if is_object_type(&expected) => Ok(Value::String(
             parse_address_literal(value)?.to_hex_literal(),
         )),
-        _ if is_string_wrapper_type(&expected) => Ok(Value::String(parse_string_literal(value)?)),
-        _ => bail!("unsupported literal parameter type `{expected_param}`"),
+        _ if is_object_vector_type(&expected) => Ok(Value::Array(
+            parse_object_vector_literal(value)?
+                .iter()
+                .map(|address| Value::String(address.to_hex_literal()))
+                .collect(),
+        )),
+        _ if is_string_wrapper_type(&expected) => {
+            let bytes = parse_string_wrapper_bytes(value)?;
+            let text = String::from_utf8(bytes).map_err(|_| {
+                anyhow!("script payload JSON cannot carry non-UTF-8 bytes for `{expected}`")
+            })?;
+            Ok(Value::String(text))
+        }
+        _ => match vector_element_type(&expected) {
+            Some(element) => Ok(Value::Array(map_vector_literal(
+                &expected,
+                value,
+                |item| normalize_literal_for_script_payload(element, item),
+            )?)),
+            None => bail!("unsupported literal parameter type `{expected_param}`"),
+        },
     }
 }
 
+/// The `T` of a (whitespace-normalized) `vector<T>` type.
+fn vector_element_type(value: &str) -> Option<&str> {
+    value
+        .strip_prefix("vector<")
+        .and_then(|inner| inner.strip_suffix('>'))
+}
+
+/// Converts each element of a JSON array literal for `vector_type`.
+fn map_vector_literal<T>(
+    vector_type: &str,
+    value: &Value,
+    mut convert: impl FnMut(&Value) -> Result<T>,
+) -> Result<Vec<T>> {
+    let items = value
+        .as_array()
+        .ok_or_else(|| anyhow!("expected {vector_type} literal as JSON array"))?;
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            convert(item)
+                .with_context(|| format!("{vector_type} element at index {index} is invalid"))
+        })
+        .collect()
+}
+
 fn normalize_type_name(value: &str) -> String {
     value.chars().filter(|ch| !ch.is_whitespace()).collect()
 }
 
+/// Parameter types (whitespace-normalized) that a `signer` argument can fill.
+const SIGNER_PARAM_TYPES: [&str; 3] = ["signer", "&signer", "&mutsigner"];
+
+fn is_signer_param(expected_param: &str) -> bool {
+    SIGNER_PARAM_TYPES.contains(&normalize_type_name(expected_param).as_str())
+}
+
 fn is_object_type(value: &str) -> bool {
     value.starts_with("0x1::object::Object<") && value.ends_with('>')
 }
 
+fn is_object_vector_type(value: &str) -> bool {
+    vector_element_type(value).is_some_and(is_object_type)
+}
+
 fn is_string_wrapper_type(value: &str) -> bool {
     matches!(value, "0x1::string::String" | "0x1::ascii::String")
 }
@@ -671,14 +1277,28 @@ fn parse_bool_literal(value: &Value) -> Result<bool> {
         .ok_or_else(|| anyhow!("expected boolean literal"))
 }
 
-fn parse_string_literal(value: &Value) -> Result<String> {
-    let text = value
-        .as_str()
-        .ok_or_else(|| anyhow!("expected string literal"))?;
-    Ok(text.to_owned())
+/// A plain JSON string is always the text itself (never hex-decoded);
+/// `{"bytes": ..}` passes raw bytes as for `vector<u8>`.
+fn parse_string_wrapper_bytes(value: &Value) -> Result<Vec<u8>> {
+    match value {
+        Value::String(text) => Ok(text.clone().into_bytes()),
+        _ if explicit_bytes(value).is_some() => parse_bytes_literal(value),
+        _ => bail!("expected string literal or {{\"bytes\": \"0x..\"}}"),
+    }
+}
+
+/// The inner value of the explicit `{"bytes": ..}` form.
+fn explicit_bytes(value: &Value) -> Option<&Value> {
+    match value {
+        Value::Object(map) if map.len() == 1 => map.get("bytes"),
+        _ => None,
+    }
 }
 
 fn parse_bytes_literal(value: &Value) -> Result<Vec<u8>> {
+    if let Some(inner) = explicit_bytes(value) {
+        return parse_bytes_literal(inner);
+    }
     match value {
         Value::String(s) => {
             let text = s.trim();
@@ -702,6 +1322,97 @@ fn parse_bytes_literal(value: &Value) -> Result<Vec<u8>> {
     }
 }
 
+/// Parses a `FixedPoint32` literal into its raw `u64` value.
+///
+/// A number or numeric string is the real value (e.g. `"1.5"`) and is scaled by
+/// `2^32`, truncating like `fixed_point32::create_from_rational`. Pass
+/// `{"raw": "6442450944"}` to supply the already-scaled wrapped integer.
+fn parse_fixed_point32_literal(value: &Value) -> Result<u64> {
+    let raw = parse_fixed_point_literal(value, 32, "FixedPoint32")?;
+    u64::try_from(raw).map_err(|_| anyhow!("FixedPoint32 literal does not fit in u64"))
+}
+
+/// Parses a `FixedPoint64` literal into its raw `u128` value; see
+/// [`parse_fixed_point32_literal`] for the accepted forms.
+fn parse_fixed_point64_literal(value: &Value) -> Result<u128> {
+    parse_fixed_point_literal(value, 64, "FixedPoint64")
+}
+
+fn parse_fixed_point_literal(value: &Value, fraction_bits: u32, type_name: &str) -> Result<u128> {
+    if let Value::Object(fields) = value {
+        if fields.len() != 1 {
+            bail!("{type_name} literal object must contain only a `raw` field");
+        }
+        let raw = fields
+            .get("raw")
+            .ok_or_else(|| anyhow!("{type_name} literal object must contain a `raw` field"))?;
+        return parse_number::<u128>(raw, "u128");
+    }
+
+    let text = normalize_numeric_literal(value)?;
+    let (int_text, fraction_text) = text.split_once('.').unwrap_or((text.as_str(), ""));
+    if int_text.is_empty() && fraction_text.is_empty() {
+        bail!("invalid {type_name} literal `{text}`");
+    }
+    if !int_text
+        .chars()
+        .chain(fraction_text.chars())
+        .all(|ch| ch.is_ascii_digit())
+    {
+        bail!("invalid {type_name} literal `{text}`: expected a non-negative decimal");
+    }
+    if fraction_text.len() > MAX_FIXED_POINT_FRACTION_DIGITS {
+        bail!(
+            "{type_name} literal `{text}` has more than {MAX_FIXED_POINT_FRACTION_DIGITS} fractional digits"
+        );
+    }
+
+    let int_part = if int_text.is_empty() {
+        0
+    } else {
+        int_text
+            .parse::<u128>()
+            .map_err(|err| anyhow!("invalid {type_name} literal `{text}`: {err}"))?
+    };
+    if int_part >> (128 - fraction_bits) != 0 {
+        bail!("{type_name} literal `{text}` is out of range");
+    }
+
+    // Binary long division of the decimal fraction yields floor(fraction * 2^bits).
+    let denominator = 10u128.pow(fraction_text.len() as u32);
+    let mut remainder = if fraction_text.is_empty() {
+        0
+    } else {
+        fraction_text.parse::<u128>()?
+    };
+    let mut fraction_raw = 0u128;
+    for _ in 0..fraction_bits {
+        remainder *= 2;
+        fraction_raw <<= 1;
+        if remainder >= denominator {
+            fraction_raw |= 1;
+            remainder -= denominator;
+        }
+    }
+
+    Ok((int_part << fraction_bits) | fraction_raw)
+}
+
+fn parse_object_vector_literal(value: &Value) -> Result<Vec<AccountAddress>> {
+    let items = value
+        .as_array()
+        .ok_or_else(|| anyhow!("expected vector<Object<T>> literal as array of address strings"))?;
+    items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| {
+            parse_address_literal(item).with_context(|| {
+                format!("vector<Object<T>> element at index {index} is not a valid address")
+            })
+        })
+        .collect()
+}
+
 fn parse_address_literal(value: &Value) -> Result<AccountAddress> {
     let raw = value
         .as_str()
@@ -721,8 +1432,50 @@ where
     <T as FromStr>::Err: std::fmt::Display,
 {
     let text = normalize_numeric_literal(value)?;
-    text.parse::<T>()
-        .map_err(|err| anyhow!("invalid {type_name} literal `{text}`: {err}"))
+    text.parse::<T>().map_err(|err| {
+        let Some((min, max)) = integer_bounds(type_name).filter(|_| is_integer_text(&text)) else {
+            return anyhow!("invalid {type_name} literal `{text}`: {err}");
+        };
+        match text.strip_prefix('-') {
+            Some(_) if min == "0" => {
+                anyhow!("value {text} is negative but {type_name} is unsigned")
+            }
+            Some(_) => anyhow!("value {text} does not fit in {type_name} (min {min})"),
+            None => anyhow!("value {text} does not fit in {type_name} (max {max})"),
+        }
+    })
+}
+
+/// Decimal `(min, max)` of a Move integer type, for range errors.
+fn integer_bounds(type_name: &str) -> Option<(String, String)> {
+    let bounds = |min: &dyn ToString, max: &dyn ToString| Some((min.to_string(), max.to_string()));
+    match type_name {
+        "u8" => bounds(&u8::MIN, &u8::MAX),
+        "u16" => bounds(&u16::MIN, &u16::MAX),
+        "u32" => bounds(&u32::MIN, &u32::MAX),
+        "u64" => bounds(&u64::MIN, &u64::MAX),
+        "u128" => bounds(&u128::MIN, &u128::MAX),
+        "u256" => bounds(
+            &0,
+            &"115792089237316195423570985008687907853269984665640564039457584007913129639935",
+        ),
+        "i8" => bounds(&i8::MIN, &i8::MAX),
+        "i16" => bounds(&i16::MIN, &i16::MAX),
+        "i32" => bounds(&i32::MIN, &i32::MAX),
+        "i64" => bounds(&i64::MIN, &i64::MAX),
+        "i128" => bounds(&i128::MIN, &i128::MAX),
+        "i256" => bounds(
+            &"-57896044618658097711785492504343953926634992332820282019728792003956564819968",
+            &"57896044618658097711785492504343953926634992332820282019728792003956564819967",
+        ),
+        _ => None,
+    }
+}
+
+/// Whether `text` is a plain decimal integer, so a parse failure means overflow.
+fn is_integer_text(text: &str) -> bool {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit())
 }
 
 fn normalize_numeric_literal(value: &Value) -> Result<String> {
@@ -783,6 +1536,547 @@ impl FunctionId {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use move_binary_format::file_format::{
+        basic_test_module, Bytecode, CodeUnit, FunctionHandleIndex, IdentifierIndex, Signature,
+        SignatureIndex, SignatureToken, Visibility,
+    };
+
+    const FIXTURE_ADDRESS: &str = "0xcafe";
+    const FIXTURE_MODULE: &str = "fixture";
+
+    /// Serves modules from memory so `compose` runs without a node.
+    struct InMemoryModuleSource {
+        modules: HashMap<ModuleId, (Vec<u8>, HashMap<String, Vec<String>>)>,
+        return_counts: HashMap<String, usize>,
+    }
+
+    impl ModuleSource for InMemoryModuleSource {
+        fn module_info(&self, module_id: &ModuleId) -> Result<ModuleInfo> {
+            let (bytecode, functions) = self
+                .modules
+                .get(module_id)
+                .ok_or_else(|| anyhow!("module {module_id} is not in the fixture set"))?;
+            // Fixture functions are all non-generic.
+            Ok(ModuleInfo {
+                bytecode: bytecode.clone(),
+                functions: functions.clone(),
+                type_param_counts: functions.keys().map(|name| (name.clone(), 0)).collect(),
+                return_counts: self.return_counts.clone(),
+            })
+        }
+    }
+
+    /// Builds `0xcafe::fixture` with `public fun one(): u64` and
+    /// `public fun consume(a: u64, b: u64)`.
+    fn fixture_module_source() -> InMemoryModuleSource {
+        let mut module = basic_test_module();
+        module.address_identifiers[0] = AccountAddress::from_hex_literal(FIXTURE_ADDRESS).unwrap();
+        module.identifiers[0] = Identifier::new(FIXTURE_MODULE).unwrap();
+
+        let empty = SignatureIndex(
+            module
+                .signatures
+                .iter()
+                .position(|signature| signature.0.is_empty())
+                .unwrap() as u16,
+        );
+        module.signatures.push(Signature(vec![SignatureToken::U64]));
+        let one_u64 = SignatureIndex(module.signatures.len() as u16 - 1);
+        module
+            .signatures
+            .push(Signature(vec![SignatureToken::U64, SignatureToken::U64]));
+        let two_u64 = SignatureIndex(module.signatures.len() as u16 - 1);
+
+        module.function_defs[0].code = Some(CodeUnit {
+            locals: empty,
+            code: vec![Bytecode::Ret],
+        });
+        let template_handle = module.function_handles[0].clone();
+        let template_def = module.function_defs[0].clone();
+        let functions = [
+            (
+                "one",
+                empty,
+                one_u64,
+                vec![Bytecode::LdU64(1), Bytecode::Ret],
+            ),
+            (
+                "consume",
+                two_u64,
+                empty,
+                vec![
+                    Bytecode::MoveLoc(0),
+                    Bytecode::Pop,
+                    Bytecode::MoveLoc(1),
+                    Bytecode::Pop,
+                    Bytecode::Ret,
+                ],
+            ),
+        ];
+        for (name, parameters, return_, code) in functions {
+            let mut handle = template_handle.clone();
+            handle.name = IdentifierIndex(module.identifiers.len() as u16);
+            handle.parameters = parameters;
+            handle.return_ = return_;
+            module.identifiers.push(Identifier::new(name).unwrap());
+            module.function_handles.push(handle);
+
+            let mut def = template_def.clone();
+            def.function = FunctionHandleIndex(module.function_handles.len() as u16 - 1);
+            def.visibility = Visibility::Public;
+            def.code = Some(CodeUnit {
+                locals: empty,
+                code,
+            });
+            module.function_defs.push(def);
+        }
+
+        let mut bytecode = Vec::new();
+        module.serialize(&mut bytecode).unwrap();
+        let abi = HashMap::from([
+            ("one".to_owned(), Vec::new()),
+            (
+                "consume".to_owned(),
+                vec!["u64".to_owned(), "u64".to_owned()],
+            ),
+        ]);
+        let module_id =
+            ModuleId::from_str(&format!("{FIXTURE_ADDRESS}::{FIXTURE_MODULE}")).unwrap();
+        InMemoryModuleSource {
+            modules: HashMap::from([(module_id, (bytecode, abi))]),
+            return_counts: HashMap::from([("one".to_owned(), 1), ("consume".to_owned(), 0)]),
+        }
+    }
+
+    #[test]
+    fn composes_two_step_batched_call_from_local_modules() {
+        let raw = json!([
+            {
+                "label": "s1",
+                "function": "0xcafe::fixture::one",
+                "args": []
+            },
+            {
+                "label": "s2",
+                "function": "0xcafe::fixture::consume",
+                "args": [
+                    {"kind": "ref", "step": "s1", "returnIndex": 0},
+                    {"kind": "literal", "value": "5"}
+                ]
+            }
+        ]);
+        let steps = parse_steps_payload(raw).unwrap();
+        let composed = compose(steps, &fixture_module_source(), false).unwrap();
+
+        let script: SerializedScriptOutput = bcs::from_bytes(&composed.script_bytes).unwrap();
+        assert!(!script.code.is_empty());
+        assert_eq!(script.args.len(), 1);
+        assert_eq!(
+            composed.payload_arguments.as_ref().unwrap(),
+            &vec![json!("5")]
+        );
+        assert_eq!(
+            composed.call_summaries[1],
+            "step `s2`: 0xcafe::fixture::consume\n  arg 0: u64 <- ref `s1` return 0\n  arg 1: u64 <- literal \"5\""
+        );
+
+        let payload = script_payload_json(&composed).unwrap();
+        assert_eq!(payload["type"], "script_payload");
+        assert_eq!(payload["arguments"], json!(["5"]));
+
+        let both = both_encodings_json(&composed).unwrap();
+        assert_eq!(both["script_bytes_hex"], json!(script_bytes_hex(&composed)));
+        assert_eq!(both["script_payload"], payload);
+    }
+
+    #[test]
+    fn composes_non_utf8_string_literal_as_script_bytes() {
+        let mut source = fixture_module_source();
+        let (_, abi) = source.modules.values_mut().next().unwrap();
+        abi.insert(
+            "consume".to_owned(),
+            vec!["u64".to_owned(), "0x1::string::String".to_owned()],
+        );
+        let raw = json!([
+            {"label": "s1", "function": "0xcafe::fixture::one", "args": []},
+            {
+                "label": "s2",
+                "function": "0xcafe::fixture::consume",
+                "args": [
+                    {"kind": "ref", "step": "s1", "returnIndex": 0},
+                    {"kind": "literal", "value": {"bytes": "0xff"}}
+                ]
+            }
+        ]);
+        let composed = compose(parse_steps_payload(raw).unwrap(), &source, false).unwrap();
+
+        let script: SerializedScriptOutput = bcs::from_bytes(&composed.script_bytes).unwrap();
+        assert_eq!(script.args.len(), 1);
+        assert!(script_bytes_hex(&composed).starts_with("0x"));
+        let err = format!("{:#}", script_payload_json(&composed).unwrap_err());
+        assert!(err.contains("cannot carry non-UTF-8 bytes"), "{err}");
+    }
+
+    #[test]
+    fn composes_from_modules_dir_and_lists_missing_modules() {
+        let dir = std::env::temp_dir().join(format!("compose-modules-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let fixture = fixture_module_source();
+        let (bytecode, _) = fixture.modules.values().next().unwrap();
+        fs::write(dir.join("0xcafe_fixture.mv"), bytecode).unwrap();
+        let abi = json!({
+            "exposed_functions": [
+                {"name": "one", "params": [], "return": ["u64"]},
+                {"name": "consume", "params": ["u64", "u64"], "return": []}
+            ]
+        });
+        fs::write(dir.join("0xcafe_fixture.abi.json"), abi.to_string()).unwrap();
+        let source = DirModuleSource {
+            dir: dir.clone(),
+            rpc: None,
+        };
+
+        let raw = json!([
+            {"label": "s1", "function": "0xcafe::fixture::one", "args": []},
+            {
+                "label": "s2",
+                "function": "0xcafe::fixture::consume",
+                "args": [
+                    {"kind": "ref", "step": "s1", "returnIndex": 0},
+                    {"kind": "literal", "value": "5"}
+                ]
+            }
+        ]);
+        let composed = compose(parse_steps_payload(raw).unwrap(), &source, false);
+
+        let raw = json!([
+            {"label": "s1", "function": "0xbeef::missing::f", "args": []},
+            {"label": "s2", "function": "0xcafe::other::g", "args": []}
+        ]);
+        let missing = compose(parse_steps_payload(raw).unwrap(), &source, false);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            composed.unwrap().payload_arguments.unwrap(),
+            vec![json!("5")]
+        );
+        let err = missing.unwrap_err().to_string();
+        assert!(err.starts_with("failed to resolve 2 module(s):"));
+        assert!(err.contains("0xbeef_missing.mv"));
+        assert!(err.contains("0xcafe_other.mv"));
+    }
+
+    #[test]
+    fn rejects_self_and_forward_refs() {
+        let raw = json!([{
+            "label": "s1",
+            "function": "0xcafe::fixture::consume",
+            "args": [
+                {"kind": "ref", "step": "s1", "returnIndex": 0},
+                {"kind": "literal", "value": "5"}
+            ]
+        }]);
+        let err = resolve_steps(parse_steps_payload(raw).unwrap()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "payload has 1 problem(s):\n  step 0 arg 0: references its own step `s1`; expected the label of a previous step"
+        );
+
+        let raw = json!([
+            {
+                "label": "s1",
+                "function": "0xcafe::fixture::consume",
+                "args": [
+                    {"kind": "literal", "value": "5"},
+                    {"kind": "ref", "step": "s2", "returnIndex": 0}
+                ]
+            },
+            {"label": "s2", "function": "0xcafe::fixture::one", "args": []}
+        ]);
+        let err = resolve_steps(parse_steps_payload(raw).unwrap()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "payload has 1 problem(s):\n  step 0 arg 1: references `s2` (step 1), which is defined later; expected the label of a previous step"
+        );
+    }
+
+    #[test]
+    fn rejects_empty_function() {
+        let raw = json!([{"label": "s1", "function": " ", "args": []}]);
+        let err = resolve_steps(parse_steps_payload(raw).unwrap()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "payload has 1 problem(s):\n  step 0: `function` is empty; expected `<address>::<module>::<function>`"
+        );
+    }
+
+    #[test]
+    fn validate_payload_collects_every_problem() {
+        let raw = json!([
+            {
+                "label": "s1",
+                "function": "",
+                "args": [{"kind": "ref", "step": "s1", "returnIndex": 0}]
+            },
+            {
+                "label": "s2",
+                "function": "0xcafe::fixture::consume",
+                "args": [
+                    {"kind": "ref", "step": "s3", "returnIndex": 0},
+                    {"kind": "ref", "step": "nope", "returnIndex": 0}
+                ]
+            },
+            {"label": "s3", "function": "0xcafe::fixture::one", "args": []},
+            {"label": "s4", "function": "0xcafe::fixture::one", "args": [{"kind": "bogus"}]}
+        ]);
+        let steps: Vec<(usize, StepInput)> = raw
+            .as_array()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, item)| Some((index, serde_json::from_value(item.clone()).ok()?)))
+            .collect();
+        let issues: Vec<String> = collect_step_issues(&steps, &|_: &FunctionId| None)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            issues,
+            [
+                "step 0: `function` is empty; expected `<address>::<module>::<function>`",
+                "step 0 arg 0: references its own step `s1`; expected the label of a previous step",
+                "step 1 arg 0: references `s3` (step 2), which is defined later; expected the label of a previous step",
+                "step 1 arg 1: references unknown step `nope`; expected the label of a previous step",
+            ]
+        );
+
+        let err = validate_payload(raw, None).unwrap_err().to_string();
+        assert!(err.starts_with("payload has 5 problem(s):"), "{err}");
+        assert!(
+            err.contains("\n  step 3: ") && err.contains("bogus"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn validate_payload_checks_return_index_against_known_abi() {
+        let raw = json!([
+            {"label": "s1", "function": "0xcafe::fixture::one", "args": []},
+            {
+                "label": "s2",
+                "function": "0xcafe::fixture::consume",
+                "args": [
+                    {"kind": "ref", "step": "s1", "returnIndex": 1},
+                    {"kind": "ref", "step": "s1", "returnIndex": 0}
+                ]
+            }
+        ]);
+        let steps: Vec<(usize, StepInput)> = serde_json::from_value::<Vec<StepInput>>(raw.clone())
+            .unwrap()
+            .into_iter()
+            .enumerate()
+            .collect();
+        let issues = collect_step_issues(&steps, &|function_id: &FunctionId| {
+            (function_id.function == "one").then_some(1)
+        });
+        assert_eq!(
+            issues,
+            [PayloadIssue::arg(
+                1,
+                0,
+                "return index 1 of `s1` is out of range; it returns 1 value"
+            )]
+        );
+        assert_eq!(validate_payload(raw, None).unwrap(), 2);
+    }
+
+    #[test]
+    fn rejects_ref_past_referenced_step_return_arity() {
+        let raw = json!([
+            {"label": "s1", "function": "0xcafe::fixture::one", "args": []},
+            {
+                "label": "s2",
+                "function": "0xcafe::fixture::consume",
+                "args": [
+                    {"kind": "ref", "step": "s1", "returnIndex": 1},
+                    {"kind": "literal", "value": "5"}
+                ]
+            }
+        ]);
+        let err = compose(
+            parse_steps_payload(raw).unwrap(),
+            &fixture_module_source(),
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "step `s2` references return index 1 of `s1` which returns 1 value"
+        );
+    }
+
+    #[test]
+    fn accepts_signer_by_value_and_by_reference() {
+        assert!(is_signer_param("signer"));
+        assert!(is_signer_param("&signer"));
+        assert!(is_signer_param("&mut signer"));
+        assert!(is_signer_param("& mut  signer"));
+        assert!(!is_signer_param("address"));
+        assert!(!is_signer_param("vector<signer>"));
+    }
+
+    #[test]
+    fn copies_type_arguments_from_previous_step() {
+        let raw = json!([
+            {
+                "label": "s1",
+                "function": "0x1::coin::withdraw",
+                "typeArguments": ["0x1::aptos_coin::AptosCoin"],
+                "args": []
+            },
+            {
+                "label": "s2",
+                "function": "0x1::coin::deposit",
+                "typeArgumentsFrom": "s1",
+                "args": []
+            },
+            {
+                "label": "s3",
+                "function": "0x1::coin::deposit",
+                "typeArgumentsFrom": "s2",
+                "args": []
+            }
+        ]);
+        let steps = resolve_steps(parse_steps_payload(raw).unwrap()).unwrap();
+        assert_eq!(steps[1].type_arguments, vec!["0x1::aptos_coin::AptosCoin"]);
+        assert_eq!(steps[2].type_arguments, vec!["0x1::aptos_coin::AptosCoin"]);
+    }
+
+    #[test]
+    fn rejects_unknown_or_conflicting_type_arguments_from() {
+        let unknown = json!([{
+            "label": "s1",
+            "function": "0x1::coin::deposit",
+            "typeArgumentsFrom": "missing",
+            "args": []
+        }]);
+        let err = resolve_steps(parse_steps_payload(unknown).unwrap()).unwrap_err();
+        assert!(err.to_string().contains("typeArgumentsFrom"));
+
+        let conflicting = json!([
+            {"label": "s1", "function": "0x1::coin::withdraw", "typeArguments": ["u64"], "args": []},
+            {
+                "label": "s2",
+                "function": "0x1::coin::deposit",
+                "typeArguments": ["u64"],
+                "typeArgumentsFrom": "s1",
+                "args": []
+            }
+        ]);
+        assert!(resolve_steps(parse_steps_payload(conflicting).unwrap()).is_err());
+    }
+
+    #[test]
+    fn rejects_type_argument_count_mismatch_against_abi() {
+        let raw = json!([{
+            "label": "s1",
+            "function": "0xcafe::fixture::one",
+            "typeArguments": ["u64"],
+            "args": []
+        }]);
+        let err = compose(
+            parse_steps_payload(raw).unwrap(),
+            &fixture_module_source(),
+            false,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("type argument count mismatch"));
+    }
+
+    #[test]
+    fn argument_count_mismatch_lists_expected_types_and_provided_kinds() {
+        let raw = json!([{
+            "label": "s1",
+            "function": "0xcafe::fixture::consume",
+            "args": [{"kind": "literal", "value": "1"}]
+        }]);
+        let err = compose(
+            parse_steps_payload(raw).unwrap(),
+            &fixture_module_source(),
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "step `s1` argument count mismatch: function expects 2 (u64, u64), payload provides 1 (literal)"
+        );
+    }
+
+    #[test]
+    fn rejects_signer_args_outside_leading_signer_params() {
+        let raw = json!([{
+            "label": "s1",
+            "function": "0x1::aptos_account::transfer",
+            "args": [
+                {"kind": "signer"},
+                {"kind": "signer"},
+                {"kind": "literal", "value": "1"}
+            ]
+        }]);
+        let step = resolve_steps(parse_steps_payload(raw).unwrap())
+            .unwrap()
+            .remove(0);
+        let params = ["&signer", "address", "u64"].map(str::to_owned);
+        let err = check_signer_positions(&step, &params).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "step `s1` arg 1 is a signer but the function takes 1 leading signer parameter(s) (&signer, address, u64)"
+        );
+
+        let params = ["&signer", "&signer", "u64"].map(str::to_owned);
+        assert!(check_signer_positions(&step, &params).is_ok());
+    }
+
+    #[test]
+    fn payload_schema_describes_steps_and_all_arg_kinds() {
+        let schema = payload_schema().to_value();
+        let defs = &schema["$defs"];
+        assert_eq!(schema["type"], "array");
+        assert_eq!(defs["ArgInput"]["oneOf"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            defs["StepInput"]["required"],
+            json!(["label", "function", "args"])
+        );
+        assert_eq!(defs["StepInput"]["additionalProperties"], false);
+        assert_eq!(
+            defs["RefArgInput"]["required"],
+            json!(["kind", "step", "returnIndex"])
+        );
+    }
+
+    #[test]
+    fn ignores_step_description_but_rejects_other_unknown_fields() {
+        let annotated = json!([{
+            "label": "s1",
+            "description": "withdraw 1 octa",
+            "function": "0x1::coin::withdraw",
+            "args": [{"kind": "signer"}]
+        }, {
+            "label": "s2",
+            "note": "aliases description",
+            "function": "0x1::coin::withdraw",
+            "args": [{"kind": "signer"}]
+        }]);
+        assert_eq!(parse_steps_payload(annotated).unwrap().len(), 2);
+
+        let typo = json!([{
+            "label": "s1",
+            "descripton": "typo",
+            "function": "0x1::coin::withdraw",
+            "args": []
+        }]);
+        assert!(parse_steps_payload(typo).is_err());
+    }
 
     #[test]
     fn rejects_tokens_field() {
@@ -840,34 +2134,34 @@ mod tests {
     }
 
     #[test]
-    fn substitutes_generic_placeholders() {
-        let actual = substitute_type_parameters(
-            "0x1::object::Object<T0>",
-            &["0x1::fungible_asset::Metadata".to_owned()],
-        );
-        assert_eq!(actual, "0x1::object::Object<0x1::fungible_asset::Metadata>");
+    fn encodes_u64_with_bigint_suffix() {
+        let bytes = encode_literal("u64", &Value::String("205000000n".to_owned())).unwrap();
+        let expected = MoveValue::U64(205_000_000).simple_serialize().unwrap();
+        assert_eq!(bytes, expected);
     }
 
     #[test]
-    fn substitutes_generics_without_touching_identifier_text() {
-        let actual = substitute_type_parameters(
-            "vector<T1>",
-            &["u8".to_owned(), "0x1::my::T0Coin".to_owned()],
+    fn reports_unsigned_overflow_with_type_max() {
+        let err = encode_literal("u8", &json!("300")).unwrap_err();
+        assert_eq!(err.to_string(), "value 300 does not fit in u8 (max 255)");
+
+        let err = encode_literal("u16", &json!(70000)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "value 70000 does not fit in u16 (max 65535)"
         );
-        assert_eq!(actual, "vector<0x1::my::T0Coin>");
     }
 
     #[test]
-    fn substitutes_only_token_placeholders() {
-        let actual = substitute_type_parameters("0x1::my::T0Coin<T0>", &["u8".to_owned()]);
-        assert_eq!(actual, "0x1::my::T0Coin<u8>");
-    }
+    fn reports_negative_values_for_unsigned_types() {
+        let err = encode_literal("u64", &json!("-1")).unwrap_err();
+        assert_eq!(err.to_string(), "value -1 is negative but u64 is unsigned");
 
-    #[test]
-    fn encodes_u64_with_bigint_suffix() {
-        let bytes = encode_literal("u64", &Value::String("205000000n".to_owned())).unwrap();
-        let expected = MoveValue::U64(205_000_000).simple_serialize().unwrap();
-        assert_eq!(bytes, expected);
+        let err = encode_literal("i8", &json!(-129)).unwrap_err();
+        assert_eq!(err.to_string(), "value -129 does not fit in i8 (min -128)");
+
+        let err = encode_literal("u8", &json!("12abc")).unwrap_err();
+        assert!(err.to_string().starts_with("invalid u8 literal `12abc`"));
     }
 
     #[test]
@@ -880,6 +2174,102 @@ mod tests {
         assert_eq!(bytes, expected);
     }
 
+    #[test]
+    fn encodes_fixed_point32_from_decimal() {
+        let bytes = encode_literal(FIXED_POINT32_TYPE, &Value::String("1.5".to_owned())).unwrap();
+        assert_eq!(bytes, vec![0, 0, 0, 0x80, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn encodes_fixed_point32_truncating_inexact_fraction() {
+        let bytes = encode_literal(FIXED_POINT32_TYPE, &json!(0.1)).unwrap();
+        let expected = MoveValue::U64(429_496_729).simple_serialize().unwrap();
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn encodes_fixed_point64_from_raw() {
+        let bytes = encode_literal(FIXED_POINT64_TYPE, &json!({"raw": "1"})).unwrap();
+        let mut expected = vec![0u8; 16];
+        expected[0] = 1;
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn rejects_fixed_point32_out_of_range() {
+        assert!(encode_literal(FIXED_POINT32_TYPE, &json!("4294967296")).is_err());
+        assert!(encode_literal(FIXED_POINT32_TYPE, &json!("-1")).is_err());
+    }
+
+    #[test]
+    fn normalizes_fixed_point32_for_script_payload() {
+        let value =
+            normalize_literal_for_script_payload(FIXED_POINT32_TYPE, &json!("1.5")).unwrap();
+        assert_eq!(value, json!({"value": "6442450944"}));
+    }
+
+    #[test]
+    fn encodes_object_vector_as_address_vector() {
+        let value = json!(["0x1", "0x2"]);
+        let bytes = encode_literal("vector<0x1::object::Object<T0>>", &value).unwrap();
+        let expected = MoveValue::Vector(vec![
+            MoveValue::Address(AccountAddress::ONE),
+            MoveValue::Address(AccountAddress::TWO),
+        ])
+        .simple_serialize()
+        .unwrap();
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn normalizes_object_vector_for_script_payload() {
+        let value = normalize_literal_for_script_payload(
+            "vector<0x1::object::Object<0x1::fungible_asset::Metadata>>",
+            &json!(["0x1", "0xa"]),
+        )
+        .unwrap();
+        assert_eq!(value, json!(["0x1", "0xa"]));
+    }
+
+    #[test]
+    fn encodes_nested_vector_literals() {
+        let bytes = encode_literal("vector<address>", &json!(["0x1", "0x2"])).unwrap();
+        let expected = MoveValue::Vector(vec![
+            MoveValue::Address(AccountAddress::ONE),
+            MoveValue::Address(AccountAddress::TWO),
+        ])
+        .simple_serialize()
+        .unwrap();
+        assert_eq!(bytes, expected);
+
+        let bytes = encode_literal("vector<vector<u8>>", &json!(["0x0102", [3]])).unwrap();
+        let expected = MoveValue::Vector(vec![
+            MoveValue::vector_u8(vec![1, 2]),
+            MoveValue::vector_u8(vec![3]),
+        ])
+        .simple_serialize()
+        .unwrap();
+        assert_eq!(bytes, expected);
+
+        let err = encode_literal("vector<u64>", &json!(["1", "x"])).unwrap_err();
+        assert!(format!("{err:#}").contains("vector<u64> element at index 1"));
+        let err = encode_literal("vector<0x1::foo::Bar>", &json!([{}])).unwrap_err();
+        assert!(format!("{err:#}")
+            .contains("unsupported literal parameter type `vector<0x1::foo::Bar>`"));
+    }
+
+    #[test]
+    fn normalizes_nested_vectors_for_script_payload() {
+        let value =
+            normalize_literal_for_script_payload("vector<vector<u64>>", &json!([[1, "2"], []]))
+                .unwrap();
+        assert_eq!(value, json!([["1", "2"], []]));
+
+        let value =
+            normalize_literal_for_script_payload("vector<bool>", &json!([true, false])).unwrap();
+        assert_eq!(value, json!([true, false]));
+    }
+
     #[test]
     fn normalizes_u64_for_script_payload() {
         let value =
@@ -888,6 +2278,42 @@ mod tests {
         assert_eq!(value, Value::String("205000000".to_owned()));
     }
 
+    #[test]
+    fn encodes_explicit_hex_bytes_into_string_wrapper() {
+        let bytes = encode_literal("0x1::string::String", &json!({"bytes": "0xff00"})).unwrap();
+        let expected = MoveValue::vector_u8(vec![0xff, 0x00])
+            .simple_serialize()
+            .unwrap();
+        assert_eq!(bytes, expected);
+
+        // Plain strings stay text, even when they look like hex.
+        let bytes = encode_literal("0x1::string::String", &json!("0xff")).unwrap();
+        let expected = MoveValue::vector_u8(b"0xff".to_vec())
+            .simple_serialize()
+            .unwrap();
+        assert_eq!(bytes, expected);
+
+        let bytes = encode_literal("vector<u8>", &json!({"bytes": [1, 2]})).unwrap();
+        let expected = MoveValue::vector_u8(vec![1, 2]).simple_serialize().unwrap();
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn normalizes_string_wrapper_bytes_for_script_payload() {
+        let value = normalize_literal_for_script_payload(
+            "0x1::string::String",
+            &json!({"bytes": "0x6869"}),
+        )
+        .unwrap();
+        assert_eq!(value, json!("hi"));
+
+        assert!(normalize_literal_for_script_payload(
+            "0x1::string::String",
+            &json!({"bytes": "0xff"})
+        )
+        .is_err());
+    }
+
     #[test]
     fn normalizes_object_for_script_payload() {
         let value = normalize_literal_for_script_payload(